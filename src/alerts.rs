@@ -0,0 +1,392 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::catalog::{self, Catalog, Text};
+use crate::config::Config;
+
+/// Built-in rule reading `[sources.prometheus.queries]` and `[thresholds]`,
+/// with the messages of the catalog
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertPreset {
+    BatteryFull,
+    BatteryLow,
+    Frost,
+    Storm,
+}
+
+impl AlertPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertPreset::BatteryFull => "battery_full",
+            AlertPreset::BatteryLow => "battery_low",
+            AlertPreset::Frost => "frost",
+            AlertPreset::Storm => "storm",
+        }
+    }
+
+    fn texts(&self) -> (Text, Text) {
+        match self {
+            AlertPreset::BatteryFull => (Text::AlertBatteryFull, Text::AlertBatteryFullResolved),
+            AlertPreset::BatteryLow => (Text::AlertBatteryLow, Text::AlertBatteryLowResolved),
+            AlertPreset::Frost => (Text::AlertFrost, Text::AlertFrostResolved),
+            AlertPreset::Storm => (Text::AlertStorm, Text::AlertStormResolved),
+        }
+    }
+}
+
+/// Condition notified as soon as it starts, apart from the announcements
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleConfig {
+    /// Built-in rule, the other keys then replace its settings
+    pub preset: Option<AlertPreset>,
+    /// Key of the rule in the state file
+    pub name: String,
+    /// Source of the query, `prometheus` or the name of a `[sources.<name>]`
+    pub source: String,
+    pub query: String,
+    /// The alert starts when the value, once rounded, crosses one of these
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    pub at_least: Option<f64>,
+    pub at_most: Option<f64>,
+    /// Value the alert must come back past to be resolved, so that a value
+    /// hovering around the threshold does not flap; the threshold when unset
+    pub clear: Option<f64>,
+    /// Digits kept after the decimal point
+    pub decimals: u32,
+    /// Sent when the alert starts, `{name}` and `{value}` are replaced
+    pub message: String,
+    /// Sent when the alert is resolved, none when unset or empty
+    pub resolved: Option<String>,
+    /// Seconds before an alert still active is sent again, `[alerts] renotify` when unset
+    pub renotify: Option<u64>,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        RuleConfig {
+            preset: None,
+            name: String::new(),
+            source: "prometheus".to_string(),
+            query: String::new(),
+            above: None,
+            below: None,
+            at_least: None,
+            at_most: None,
+            clear: None,
+            decimals: 0,
+            message: String::new(),
+            resolved: None,
+            renotify: None,
+        }
+    }
+}
+
+/// Rules checked when the configuration lists none
+pub fn default_rules() -> Vec<RuleConfig> {
+    [
+        AlertPreset::BatteryFull,
+        AlertPreset::BatteryLow,
+        AlertPreset::Frost,
+        AlertPreset::Storm,
+    ]
+    .into_iter()
+    .map(|preset| RuleConfig {
+        preset: Some(preset),
+        ..RuleConfig::default()
+    })
+    .collect()
+}
+
+impl RuleConfig {
+    fn limits(&self) -> [Option<f64>; 4] {
+        [self.above, self.below, self.at_least, self.at_most]
+    }
+
+    /// Whether the alert fires on high values
+    fn rising(&self) -> bool {
+        self.above.is_some() || self.at_least.is_some()
+    }
+
+    fn crossed(&self, value: f64) -> bool {
+        self.above.is_some_and(|l| value > l)
+            || self.below.is_some_and(|l| value < l)
+            || self.at_least.is_some_and(|l| value >= l)
+            || self.at_most.is_some_and(|l| value <= l)
+    }
+
+    fn cleared(&self, value: f64) -> bool {
+        match self.clear {
+            Some(clear) if self.rising() => value < clear,
+            Some(clear) => value > clear,
+            None => !self.crossed(value),
+        }
+    }
+
+    /// Rule with the settings of its preset filled in where unset
+    pub fn resolve(&self, config: &Config) -> RuleConfig {
+        let preset = match self.preset {
+            Some(preset) => preset,
+            None => return self.clone(),
+        };
+        let queries = &config.sources.prometheus.queries;
+        let decimals = &config.sources.prometheus.decimals;
+        let t = &config.thresholds;
+
+        let (query, decimals, limits, clear) = match preset {
+            AlertPreset::BatteryFull => (
+                &queries.battery_soc,
+                decimals.battery_soc,
+                [None, None, Some(t.battery_full as f64), None],
+                t.battery_full as f64 - 5.0,
+            ),
+            AlertPreset::BatteryLow => (
+                &queries.battery_soc,
+                decimals.battery_soc,
+                [None, Some(t.battery_low as f64), None, None],
+                t.battery_low as f64 + 5.0,
+            ),
+            AlertPreset::Frost => (
+                &queries.temperature_min,
+                decimals.temperature_min,
+                [None, Some(t.cold_temperature as f64), None, None],
+                t.cold_temperature as f64 + 2.0,
+            ),
+            AlertPreset::Storm => (
+                &queries.wind_speed,
+                decimals.wind_speed,
+                [Some(t.strong_wind as f64), None, None, None],
+                t.strong_wind as f64 - 10.0,
+            ),
+        };
+        // The band of the preset only fits its own threshold
+        let (limits, clear) = if self.limits().iter().any(Option::is_some) {
+            (self.limits(), self.clear)
+        } else {
+            (limits, self.clear.or(Some(clear)))
+        };
+
+        RuleConfig {
+            preset: Some(preset),
+            name: if self.name.is_empty() {
+                preset.name().to_string()
+            } else {
+                self.name.clone()
+            },
+            source: self.source.clone(),
+            query: if self.query.is_empty() {
+                query.clone()
+            } else {
+                self.query.clone()
+            },
+            above: limits[0],
+            below: limits[1],
+            at_least: limits[2],
+            at_most: limits[3],
+            clear,
+            decimals: if self.query.is_empty() {
+                decimals
+            } else {
+                self.decimals
+            },
+            message: self.message.clone(),
+            resolved: self.resolved.clone(),
+            renotify: self.renotify,
+        }
+    }
+
+    /// Check a resolved rule
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("each rule needs a name or a preset".to_string());
+        }
+        if self.query.is_empty() {
+            return Err(format!("{}: no query", self.name));
+        }
+        let limits: Vec<f64> = self.limits().into_iter().flatten().collect();
+        if limits.len() != 1 {
+            return Err(format!(
+                "{}: set one of above, below, at_least or at_most",
+                self.name
+            ));
+        }
+        if let Some(clear) = self.clear {
+            if (self.rising() && clear > limits[0]) || (!self.rising() && clear < limits[0]) {
+                return Err(format!(
+                    "{}: clear ({}) must be on the safe side of the threshold ({})",
+                    self.name, clear, limits[0]
+                ));
+            }
+        }
+        if self.preset.is_none() && self.message.is_empty() {
+            return Err(format!("{}: no message", self.name));
+        }
+        Ok(())
+    }
+
+    /// Text to send for `event`, `None` when the rule has none
+    pub fn message(&self, event: &Event, value: f64, catalog: &Catalog) -> Option<String> {
+        let resolved = *event == Event::Resolved;
+        let template = match (resolved, self.preset) {
+            (false, _) if !self.message.is_empty() => self.message.clone(),
+            (true, _) if self.resolved.is_some() => self.resolved.clone()?,
+            (false, Some(preset)) => catalog.text(preset.texts().0, &[]),
+            (true, Some(preset)) => catalog.text(preset.texts().1, &[]),
+            _ => return None,
+        };
+        if template.is_empty() {
+            return None;
+        }
+        let value = catalog.number(value, self.decimals);
+        Some(catalog::fill(
+            &template,
+            &[("name", &self.name), ("value", &value)],
+        ))
+    }
+}
+
+/// State of a rule kept between runs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RuleState {
+    pub active: bool,
+    /// Start of the current alert, or of the calm period
+    pub since: DateTime<Utc>,
+    /// Last time the alert was sent
+    pub notified_at: Option<DateTime<Utc>>,
+    pub value: f64,
+}
+
+/// What a new value means for a rule
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    Started,
+    Repeated,
+    Resolved,
+}
+
+/// Next state of a rule given its value at `now`, with the event to notify
+pub fn step(
+    rule: &RuleConfig,
+    state: Option<&RuleState>,
+    value: f64,
+    now: DateTime<Utc>,
+    renotify: u64,
+) -> (RuleState, Option<Event>) {
+    let active = state.is_some_and(|s| s.active);
+    let renotify = rule.renotify.unwrap_or(renotify);
+
+    if !active && rule.crossed(value) {
+        let state = RuleState {
+            active: true,
+            since: now,
+            notified_at: Some(now),
+            value,
+        };
+        return (state, Some(Event::Started));
+    }
+
+    let mut next = state.cloned().unwrap_or(RuleState {
+        active: false,
+        since: now,
+        notified_at: None,
+        value,
+    });
+    next.value = value;
+    if !active {
+        return (next, None);
+    }
+
+    if rule.cleared(value) {
+        next.active = false;
+        next.since = now;
+        return (next, Some(Event::Resolved));
+    }
+
+    let due = next
+        .notified_at
+        .is_none_or(|at| renotify > 0 && (now - at).num_seconds() >= renotify as i64);
+    if due {
+        next.notified_at = Some(now);
+        return (next, Some(Event::Repeated));
+    }
+    (next, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn battery_low() -> RuleConfig {
+        RuleConfig {
+            name: "battery_low".to_string(),
+            query: "imeon_battery_soc".to_string(),
+            below: Some(20.0),
+            clear: Some(25.0),
+            message: "Batterie faible : {value} %".to_string(),
+            ..RuleConfig::default()
+        }
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn hysteresis() {
+        let rule = battery_low();
+        let (state, event) = step(&rule, None, 30.0, at(0), 0);
+        assert_eq!(event, None);
+        let (state, event) = step(&rule, Some(&state), 19.0, at(1), 0);
+        assert_eq!(event, Some(Event::Started));
+        // Back above the threshold but within the band
+        let (state, event) = step(&rule, Some(&state), 22.0, at(2), 0);
+        assert_eq!(event, None);
+        assert!(state.active);
+        let (state, event) = step(&rule, Some(&state), 19.0, at(3), 0);
+        assert_eq!(event, None);
+        let (state, event) = step(&rule, Some(&state), 26.0, at(4), 0);
+        assert_eq!(event, Some(Event::Resolved));
+        assert!(!state.active);
+    }
+
+    #[test]
+    fn renotify() {
+        let rule = RuleConfig {
+            renotify: Some(3 * 3600),
+            ..battery_low()
+        };
+        let (state, _) = step(&rule, None, 10.0, at(0), 0);
+        let (state, event) = step(&rule, Some(&state), 10.0, at(2), 0);
+        assert_eq!(event, None);
+        let (state, event) = step(&rule, Some(&state), 10.0, at(3), 0);
+        assert_eq!(event, Some(Event::Repeated));
+        assert_eq!(state.notified_at, Some(at(3)));
+        assert_eq!(state.since, at(0));
+    }
+
+    #[test]
+    fn presets() {
+        let mut config = Config::default();
+        config.thresholds.strong_wind = 50.0;
+        let storm = RuleConfig {
+            preset: Some(AlertPreset::Storm),
+            ..RuleConfig::default()
+        }
+        .resolve(&config);
+        assert_eq!(storm.name, "storm");
+        assert_eq!(storm.above, Some(50.0));
+        assert_eq!(storm.clear, Some(40.0));
+        assert!(storm.validate().is_ok());
+
+        let catalog = Catalog::embedded(crate::locale::Locale::En);
+        let text = storm.message(&Event::Started, 62.0, &catalog).unwrap();
+        assert!(text.contains("62"), "{}", text);
+
+        let mut rule = battery_low();
+        rule.clear = Some(15.0);
+        assert!(rule.validate().is_err());
+    }
+}
@@ -0,0 +1,786 @@
+use chrono::Weekday;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::locale::Locale;
+
+/// Phrase categories used by the composer
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Greetings,
+    NotifDay,
+    ReportPower,
+    Full,
+    SpendElec,
+    Inject,
+    MorningGreet,
+    MotivMorning,
+    Lunch,
+    MotivAfternoon,
+    Break,
+    Dinner,
+    Goodnight,
+    NowWeather,
+    StrongWind,
+    ColdTemp,
+    HighTemp,
+}
+
+impl Category {
+    pub const ALL: [Category; 17] = [
+        Category::Greetings,
+        Category::NotifDay,
+        Category::ReportPower,
+        Category::Full,
+        Category::SpendElec,
+        Category::Inject,
+        Category::MorningGreet,
+        Category::MotivMorning,
+        Category::Lunch,
+        Category::MotivAfternoon,
+        Category::Break,
+        Category::Dinner,
+        Category::Goodnight,
+        Category::NowWeather,
+        Category::StrongWind,
+        Category::ColdTemp,
+        Category::HighTemp,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Category> {
+        Category::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// Name of the category in catalog files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Greetings => "greetings",
+            Category::NotifDay => "notif_day",
+            Category::ReportPower => "report_power",
+            Category::Full => "full",
+            Category::SpendElec => "spend_elec",
+            Category::Inject => "inject",
+            Category::MorningGreet => "morning_greet",
+            Category::MotivMorning => "motiv_morning",
+            Category::Lunch => "lunch",
+            Category::MotivAfternoon => "motiv_afternoon",
+            Category::Break => "break",
+            Category::Dinner => "dinner",
+            Category::Goodnight => "goodnight",
+            Category::NowWeather => "now_weather",
+            Category::StrongWind => "strong_wind",
+            Category::ColdTemp => "cold_temp",
+            Category::HighTemp => "high_temp",
+        }
+    }
+}
+
+/// Fixed sentences of a locale
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Text {
+    TimeExact,
+    Time,
+    Am,
+    Pm,
+    Date,
+    UnknownWeather,
+    CloudCover,
+    Temperature,
+    Wind,
+    Humidity,
+    Battery,
+    Solar,
+    Consumption,
+    Unavailable,
+    TemperatureRise,
+    TemperatureFall,
+    BatteryCharged,
+    BatteryDischarged,
+    AlertBatteryFull,
+    AlertBatteryFullResolved,
+    AlertBatteryLow,
+    AlertBatteryLowResolved,
+    AlertFrost,
+    AlertFrostResolved,
+    AlertStorm,
+    AlertStormResolved,
+    ExclamationOpen,
+    Exclamation,
+    Colon,
+}
+
+impl Text {
+    pub const ALL: [Text; 29] = [
+        Text::TimeExact,
+        Text::Time,
+        Text::Am,
+        Text::Pm,
+        Text::Date,
+        Text::UnknownWeather,
+        Text::CloudCover,
+        Text::Temperature,
+        Text::Wind,
+        Text::Humidity,
+        Text::Battery,
+        Text::Solar,
+        Text::Consumption,
+        Text::Unavailable,
+        Text::TemperatureRise,
+        Text::TemperatureFall,
+        Text::BatteryCharged,
+        Text::BatteryDischarged,
+        Text::AlertBatteryFull,
+        Text::AlertBatteryFullResolved,
+        Text::AlertBatteryLow,
+        Text::AlertBatteryLowResolved,
+        Text::AlertFrost,
+        Text::AlertFrostResolved,
+        Text::AlertStorm,
+        Text::AlertStormResolved,
+        Text::ExclamationOpen,
+        Text::Exclamation,
+        Text::Colon,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Text> {
+        Text::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    /// Name of the text in catalog files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Text::TimeExact => "time_exact",
+            Text::Time => "time",
+            Text::Am => "am",
+            Text::Pm => "pm",
+            Text::Date => "date",
+            Text::UnknownWeather => "unknown_weather",
+            Text::CloudCover => "cloud_cover",
+            Text::Temperature => "temperature",
+            Text::Wind => "wind",
+            Text::Humidity => "humidity",
+            Text::Battery => "battery",
+            Text::Solar => "solar",
+            Text::Consumption => "consumption",
+            Text::Unavailable => "unavailable",
+            Text::TemperatureRise => "temperature_rise",
+            Text::TemperatureFall => "temperature_fall",
+            Text::BatteryCharged => "battery_charged",
+            Text::BatteryDischarged => "battery_discharged",
+            Text::AlertBatteryFull => "alert_battery_full",
+            Text::AlertBatteryFullResolved => "alert_battery_full_resolved",
+            Text::AlertBatteryLow => "alert_battery_low",
+            Text::AlertBatteryLowResolved => "alert_battery_low_resolved",
+            Text::AlertFrost => "alert_frost",
+            Text::AlertFrostResolved => "alert_frost_resolved",
+            Text::AlertStorm => "alert_storm",
+            Text::AlertStormResolved => "alert_storm_resolved",
+            Text::ExclamationOpen => "exclamation_open",
+            Text::Exclamation => "exclamation",
+            Text::Colon => "colon",
+        }
+    }
+}
+
+/// How hours are spoken
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clock {
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "12h")]
+    H12,
+}
+
+/// Weather code, a string key in TOML and JSON files
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct WeatherKey(u32);
+
+impl<'de> Deserialize<'de> for WeatherKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Int(u32),
+            Str(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Int(code) => Ok(WeatherKey(code)),
+            Raw::Str(s) => s
+                .parse()
+                .map(WeatherKey)
+                .map_err(|_| de::Error::custom(format!("invalid weather code '{}'", s))),
+        }
+    }
+}
+
+type Phrases = HashMap<Category, Vec<String>>;
+
+/// How a phrase is chosen among those of its category
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    /// Any phrase, each time
+    Random,
+    /// Every phrase once, in random order, before any comes back
+    Shuffle,
+    /// The phrase used the longest time ago, or never
+    Lru,
+    /// Any phrase, in proportion to its weight
+    Weighted,
+}
+
+/// `[selection]` table of a catalog file: `default` and the categories picked
+/// another way, e.g. `spend_elec = "lru"`
+#[derive(Deserialize, Default)]
+struct SelectionTable {
+    default: Option<Selection>,
+    #[serde(flatten)]
+    categories: HashMap<Category, Selection>,
+}
+
+/// Phrase in a catalog file, a string or `{ text = "...", weight = 2.0 }`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PhraseEntry {
+    Text(String),
+    Weighted { text: String, weight: f64 },
+}
+
+impl PhraseEntry {
+    fn weight(&self) -> f64 {
+        match self {
+            PhraseEntry::Text(_) => 1.0,
+            PhraseEntry::Weighted { weight, .. } => *weight,
+        }
+    }
+}
+
+/// What the selection remembers of the previous picks, kept in the state file
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Usage {
+    /// Phrases left in the current round of each shuffle-bag
+    pub bags: BTreeMap<Category, Vec<usize>>,
+    /// Number of the pick that last used each phrase, by category
+    pub last_used: BTreeMap<Category, BTreeMap<usize, u64>>,
+    /// Picks counted in `last_used` so far
+    pub picks: u64,
+}
+
+/// Index of the next phrase of `category` among `weights.len()`, updating `usage`
+fn choose(
+    selection: Selection,
+    category: Category,
+    weights: &[f64],
+    usage: &mut Usage,
+    rng: &mut impl Rng,
+) -> usize {
+    let len = weights.len();
+    match selection {
+        Selection::Random => rng.gen_range(0..len),
+        Selection::Shuffle => {
+            let bag = usage.bags.entry(category).or_default();
+            // The category may have shrunk since the bag was filled
+            bag.retain(|&i| i < len);
+            if bag.is_empty() {
+                *bag = (0..len).collect();
+            }
+            bag.swap_remove(rng.gen_range(0..bag.len()))
+        }
+        Selection::Lru => {
+            let last_used = usage.last_used.entry(category).or_default();
+            let used = |i: usize| last_used.get(&i).copied();
+            let oldest = (0..len).map(used).min().flatten();
+            let candidates: Vec<usize> = (0..len).filter(|&i| used(i) == oldest).collect();
+            let index = candidates[rng.gen_range(0..candidates.len())];
+            usage.picks += 1;
+            last_used.insert(index, usage.picks);
+            index
+        }
+        Selection::Weighted => match WeightedIndex::new(weights) {
+            Ok(distribution) => distribution.sample(rng),
+            Err(_) => rng.gen_range(0..len),
+        },
+    }
+}
+
+/// Announcement and its rewrite, shown to the model before the real one
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Example {
+    pub input: String,
+    pub output: String,
+}
+
+/// Content of one catalog file, every key is optional
+#[derive(Deserialize, Default)]
+struct CatalogFile {
+    weekdays: Option<Vec<String>>,
+    months: Option<Vec<String>>,
+    clock: Option<Clock>,
+    decimal_separator: Option<String>,
+    #[serde(default)]
+    texts: HashMap<Text, String>,
+    #[serde(default)]
+    weather: HashMap<WeatherKey, String>,
+    examples: Option<Vec<Example>>,
+    #[serde(default)]
+    selection: SelectionTable,
+    #[serde(flatten)]
+    phrases: HashMap<Category, Vec<PhraseEntry>>,
+}
+
+impl CatalogFile {
+    fn check(&self) -> Result<(), String> {
+        for (category, list) in self.phrases.iter() {
+            if list.is_empty() {
+                return Err(format!("category '{}' is empty", category.name()));
+            }
+            if list
+                .iter()
+                .any(|p| !(p.weight() >= 0.0 && p.weight().is_finite()))
+            {
+                return Err(format!(
+                    "category '{}': weights must be positive numbers",
+                    category.name()
+                ));
+            }
+            if list.iter().all(|p| p.weight() == 0.0) {
+                return Err(format!(
+                    "category '{}': at least one phrase needs a weight above 0",
+                    category.name()
+                ));
+            }
+        }
+        if self.weekdays.as_ref().is_some_and(|w| w.len() != 7) {
+            return Err("weekdays: expected 7 names, from Monday to Sunday".to_string());
+        }
+        if self.months.as_ref().is_some_and(|m| m.len() != 12) {
+            return Err("months: expected 12 names, from January to December".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Phrase of a category picked for a message
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pick {
+    pub category: Category,
+    /// Position of the phrase in the category
+    pub index: usize,
+}
+
+/// Phrases, names and sentences of a locale
+#[derive(Debug)]
+pub struct Catalog {
+    phrases: Phrases,
+    /// Weight of each phrase, 1 unless set
+    weights: HashMap<Category, Vec<f64>>,
+    /// Selection of the categories without their own
+    selection: Selection,
+    selections: HashMap<Category, Selection>,
+    weekdays: Vec<String>,
+    months: Vec<String>,
+    clock: Clock,
+    decimal_separator: String,
+    texts: HashMap<Text, String>,
+    weather: HashMap<u32, String>,
+    examples: Vec<Example>,
+    /// Phrases picked since the last `take_picks`
+    picks: Mutex<Vec<Pick>>,
+    usage: Mutex<Usage>,
+    rng: Mutex<StdRng>,
+}
+
+fn parse_file(path: &Path) -> Result<CatalogFile, Box<dyn error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+
+    let file: CatalogFile = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| e.to_string()),
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        _ => Err("unsupported extension, expected toml, yaml, yml or json".to_string()),
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    file.check()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    Ok(file)
+}
+
+/// Extensions of the catalog files, other files are ignored
+const EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+/// Catalog files of a directory accepted by `keep`, in name order
+fn list_files(
+    dir: &Path,
+    keep: impl Fn(&Path) -> bool,
+) -> Result<Vec<std::path::PathBuf>, Box<dyn error::Error>> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| EXTENSIONS.contains(&e))
+        })
+        .filter(|p| keep(p))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Replace `{name}` by its value in `template`
+pub fn fill(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = template.to_string();
+    for (name, value) in vars {
+        out = out.replace(&format!("{{{}}}", name), value);
+    }
+    out
+}
+
+impl Catalog {
+    fn merge(&mut self, file: CatalogFile) {
+        for (category, list) in file.phrases {
+            self.weights
+                .insert(category, list.iter().map(PhraseEntry::weight).collect());
+            let list = list
+                .into_iter()
+                .map(|p| match p {
+                    PhraseEntry::Text(text) | PhraseEntry::Weighted { text, .. } => text,
+                })
+                .collect();
+            self.phrases.insert(category, list);
+        }
+        if let Some(selection) = file.selection.default {
+            self.selection = selection;
+        }
+        self.selections.extend(file.selection.categories);
+        if let Some(weekdays) = file.weekdays {
+            self.weekdays = weekdays;
+        }
+        if let Some(months) = file.months {
+            self.months = months;
+        }
+        if let Some(clock) = file.clock {
+            self.clock = clock;
+        }
+        if let Some(sep) = file.decimal_separator {
+            self.decimal_separator = sep;
+        }
+        self.texts.extend(file.texts);
+        self.weather
+            .extend(file.weather.into_iter().map(|(k, v)| (k.0, v)));
+        if let Some(examples) = file.examples {
+            self.examples = examples;
+        }
+    }
+
+    /// Catalog compiled into the binary, keys missing in the locale come from French
+    pub fn embedded(locale: Locale) -> Catalog {
+        let mut catalog = Catalog {
+            phrases: HashMap::new(),
+            weights: HashMap::new(),
+            selection: Selection::Random,
+            selections: HashMap::new(),
+            weekdays: Vec::new(),
+            months: Vec::new(),
+            clock: Clock::H24,
+            decimal_separator: ".".to_string(),
+            texts: HashMap::new(),
+            weather: HashMap::new(),
+            examples: Vec::new(),
+            picks: Mutex::new(Vec::new()),
+            usage: Mutex::new(Usage::default()),
+            rng: Mutex::new(StdRng::from_entropy()),
+        };
+
+        for l in [Locale::Fr, locale] {
+            let file: CatalogFile = toml::from_str(l.embedded_catalog())
+                .unwrap_or_else(|e| panic!("Invalid embedded {} catalog: {}", l.name(), e));
+            catalog.merge(file);
+        }
+
+        catalog
+    }
+
+    /// Embedded catalog where each key defined in a `<locale>.*` file of `dir`
+    /// (`fr.toml`, `fr.extra.yaml`…), then in a file of `dir/<locale>`,
+    /// replaces the default one. Files are read in name order, the ones
+    /// without a TOML, YAML or JSON extension are ignored.
+    pub fn load(locale: Locale, dir: &Path) -> Result<Catalog, Box<dyn error::Error>> {
+        let mut catalog = Catalog::embedded(locale);
+
+        let mut files = list_files(dir, |path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.split('.').next())
+                == Some(locale.name())
+        })?;
+        let locale_dir = dir.join(locale.name());
+        if locale_dir.is_dir() {
+            files.extend(list_files(&locale_dir, |_| true)?);
+        }
+
+        for path in files {
+            catalog.merge(parse_file(&path)?);
+        }
+
+        catalog.validate()?;
+
+        Ok(catalog)
+    }
+
+    /// Check that every category has at least one phrase and every text is set
+    pub fn validate(&self) -> Result<(), Box<dyn error::Error>> {
+        for category in Category::ALL.iter() {
+            if self.phrases.get(category).is_none_or(|l| l.is_empty()) {
+                return Err(format!("catalog: category '{}' is empty", category.name()).into());
+            }
+        }
+        for text in Text::ALL.iter() {
+            if !self.texts.contains_key(text) {
+                return Err(format!("catalog: text '{}' is missing", text.name()).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// How the phrases of `category` are picked
+    pub fn selection(&self, category: Category) -> Selection {
+        self.selections
+            .get(&category)
+            .copied()
+            .unwrap_or(self.selection)
+    }
+
+    /// Pick a phrase of the category with its selection
+    pub fn pick(&self, category: Category) -> &str {
+        let possible = &self.phrases[&category];
+        let weights = &self.weights[&category];
+        let selection = self.selection(category);
+        let index = match (self.usage.lock(), self.rng.lock()) {
+            (Ok(mut usage), Ok(mut rng)) => {
+                choose(selection, category, weights, &mut usage, &mut *rng)
+            }
+            _ => rand::thread_rng().gen_range(0..possible.len()),
+        };
+        if let Ok(mut picks) = self.picks.lock() {
+            picks.push(Pick { category, index });
+        }
+        &possible[index]
+    }
+
+    /// Phrases picked since the previous call, in order
+    pub fn take_picks(&self) -> Vec<Pick> {
+        self.picks
+            .lock()
+            .map(|mut picks| std::mem::take(&mut *picks))
+            .unwrap_or_default()
+    }
+
+    /// Make the following picks depend on `seed` only, given the same usage
+    pub fn seed(&self, seed: u64) {
+        if let Ok(mut rng) = self.rng.lock() {
+            *rng = StdRng::seed_from_u64(seed);
+        }
+    }
+
+    /// What the selection remembers, to keep for the next run
+    pub fn usage(&self) -> Usage {
+        self.usage.lock().map(|u| u.clone()).unwrap_or_default()
+    }
+
+    /// Resume the selection from what a previous run remembered
+    pub fn set_usage(&self, usage: Usage) {
+        if let Ok(mut current) = self.usage.lock() {
+            *current = usage;
+        }
+    }
+
+    pub fn weekday(&self, day: Weekday) -> &str {
+        &self.weekdays[day.num_days_from_monday() as usize]
+    }
+
+    pub fn month(&self, month: u32) -> &str {
+        &self.months[(month - 1) as usize]
+    }
+
+    /// Description of a weather code
+    pub fn weather(&self, code: u32) -> &str {
+        match self.weather.get(&code) {
+            Some(d) => d,
+            None => &self.texts[&Text::UnknownWeather],
+        }
+    }
+
+    /// Fixed sentence with its `{name}` placeholders replaced
+    pub fn text(&self, text: Text, vars: &[(&str, &str)]) -> String {
+        fill(&self.texts[&text], vars)
+    }
+
+    /// Spoken time of day
+    pub fn time(&self, hour: u32, minute: u32) -> String {
+        let (hour, period) = match self.clock {
+            Clock::H24 => (hour, ""),
+            Clock::H12 => {
+                let period = if hour < 12 { Text::Am } else { Text::Pm };
+                let hour = match hour % 12 {
+                    0 => 12,
+                    h => h,
+                };
+                (hour, self.texts[&period].as_str())
+            }
+        };
+
+        let text = if minute == 0 {
+            Text::TimeExact
+        } else {
+            Text::Time
+        };
+        self.text(
+            text,
+            &[
+                ("hour", &hour.to_string()),
+                ("minute", &minute.to_string()),
+                ("minute2", &format!("{:02}", minute)),
+                ("period", period),
+            ],
+        )
+    }
+
+    /// Few-shot examples of the rewrite prompt
+    pub fn examples(&self) -> &[Example] {
+        &self.examples
+    }
+
+    /// Number with `decimals` digits, written with the decimal separator of the locale
+    pub fn number(&self, value: f64, decimals: u32) -> String {
+        format!("{:.*}", decimals as usize, value).replace('.', &self.decimal_separator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shuffle_bag() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut usage = Usage::default();
+        let weights = [1.0; 5];
+        let pick = |usage: &mut Usage, rng: &mut StdRng| {
+            choose(
+                Selection::Shuffle,
+                Category::Greetings,
+                &weights,
+                usage,
+                rng,
+            )
+        };
+
+        let mut round: Vec<usize> = (0..5).map(|_| pick(&mut usage, &mut rng)).collect();
+        round.sort();
+        assert_eq!(round, vec![0, 1, 2, 3, 4]);
+        // The bag is refilled once empty
+        pick(&mut usage, &mut rng);
+        assert_eq!(usage.bags[&Category::Greetings].len(), 4);
+    }
+
+    #[test]
+    fn least_recently_used() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut usage = Usage::default();
+        usage
+            .last_used
+            .insert(Category::Lunch, BTreeMap::from([(0, 5), (1, 2), (2, 9)]));
+        usage.picks = 9;
+        let weights = [1.0; 4];
+
+        // Never used first, then the oldest
+        let first = choose(
+            Selection::Lru,
+            Category::Lunch,
+            &weights,
+            &mut usage,
+            &mut rng,
+        );
+        let second = choose(
+            Selection::Lru,
+            Category::Lunch,
+            &weights,
+            &mut usage,
+            &mut rng,
+        );
+        assert_eq!((first, second), (3, 1));
+        assert_eq!(usage.last_used[&Category::Lunch][&1], 11);
+    }
+
+    #[test]
+    fn weighted_phrases() {
+        let file: CatalogFile = toml::from_str(
+            r#"
+            lunch = ["Bon appétit", { text = "À table !", weight = 0 }]
+            [selection]
+            default = "lru"
+            lunch = "weighted"
+            "#,
+        )
+        .unwrap();
+        assert!(file.check().is_ok());
+
+        let mut catalog = Catalog::embedded(Locale::Fr);
+        catalog.merge(file);
+        for _ in 0..20 {
+            assert_eq!(catalog.pick(Category::Lunch), "Bon appétit");
+        }
+        assert_eq!(catalog.selection(Category::Dinner), Selection::Lru);
+
+        // A later file keeps the categories it does not mention
+        let file: CatalogFile = toml::from_str("[selection]\ndinner = \"random\"").unwrap();
+        catalog.merge(file);
+        assert_eq!(catalog.selection(Category::Lunch), Selection::Weighted);
+        assert_eq!(catalog.selection(Category::Dinner), Selection::Random);
+        assert_eq!(catalog.selection(Category::Break), Selection::Lru);
+        assert!(toml::from_str::<CatalogFile>("[selection]\nsupper = \"lru\"").is_err());
+
+        let file: CatalogFile = toml::from_str(r#"lunch = [{ text = "x", weight = -1 }]"#).unwrap();
+        assert!(file.check().is_err());
+    }
+
+    #[test]
+    fn locale_files() {
+        let dir = std::env::temp_dir().join(format!("notifier-catalog-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("en")).unwrap();
+        let write = |name: &str, content: &str| std::fs::write(dir.join(name), content).unwrap();
+        write("README.md", "# Phrases of the house");
+        write("fr.toml", r#"lunch = ["À table"]"#);
+        write("fr.old.yaml", "dinner: [\"Le dîner\"]");
+        write("en.toml", r#"lunch = ["Lunch time"]"#);
+        write("en/extra.json", r#"{"dinner": ["Dinner time"]}"#);
+        write("en/notes.txt", "not a catalog");
+
+        let catalog = Catalog::load(Locale::Fr, &dir).unwrap();
+        assert_eq!(catalog.phrases[&Category::Lunch], vec!["À table"]);
+        assert_eq!(catalog.phrases[&Category::Dinner], vec!["Le dîner"]);
+
+        let catalog = Catalog::load(Locale::En, &dir).unwrap();
+        assert_eq!(catalog.phrases[&Category::Lunch], vec!["Lunch time"]);
+        assert_eq!(catalog.phrases[&Category::Dinner], vec!["Dinner time"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,657 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::alerts::{self, RuleConfig};
+use crate::catalog::Example;
+use crate::llm::{self, BackendKind, OnTimeout};
+use crate::locale::Locale;
+use crate::schedule::{self, Slot};
+use crate::sections::{self, SectionConfig};
+use crate::sinks::SinkConfig;
+use crate::sources::SourceConfig;
+
+/// Prometheus server and the PromQL expressions used by the composers
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrometheusSource {
+    pub url: String,
+    /// Seconds after which a sample is read as missing, 0 keeps every sample
+    pub max_age: u64,
+    /// Timeout of each query in seconds
+    pub timeout: u64,
+    /// Seconds given to all the queries of an announcement, run at once
+    pub budget: u64,
+    /// User of the basic authentication, with `password`
+    pub username: Option<String>,
+    pub password: Option<Secret>,
+    /// Token sent as `Authorization: Bearer`
+    pub bearer_token: Option<Secret>,
+    /// Headers added to every query, e.g. `X-Scope-OrgID` for Mimir or Thanos
+    pub headers: BTreeMap<String, String>,
+    pub tls: Tls,
+    pub queries: Queries,
+    /// Digits kept after the decimal point, by query
+    pub decimals: Decimals,
+    pub trends: Trends,
+}
+
+impl Default for PrometheusSource {
+    fn default() -> Self {
+        PrometheusSource {
+            url: "http://127.0.0.1:9090".to_string(),
+            max_age: 0,
+            timeout: 5,
+            budget: 10,
+            username: None,
+            password: None,
+            bearer_token: None,
+            headers: BTreeMap::new(),
+            tls: Tls::default(),
+            queries: Queries::default(),
+            decimals: Decimals::default(),
+            trends: Trends::default(),
+        }
+    }
+}
+
+/// Secret kept out of the configuration file and the command line, written
+/// `{ file = "/run/secrets/token" }` or `{ env = "PROMETHEUS_TOKEN" }`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum Secret {
+    /// File holding the secret, a trailing line break is ignored
+    File(PathBuf),
+    /// Environment variable holding the secret
+    Env(String),
+}
+
+impl Secret {
+    pub fn read(&self) -> Result<String, String> {
+        match self {
+            Secret::File(path) => std::fs::read_to_string(path)
+                .map(|s| s.trim_end_matches(['\r', '\n']).to_string())
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e)),
+            Secret::Env(name) => {
+                std::env::var(name).map_err(|e| format!("environment variable {}: {}", name, e))
+            }
+        }
+    }
+}
+
+/// PEM files of a server using its own certificate authority or requiring a
+/// client certificate
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tls {
+    /// Certificate authority trusted in addition to the system ones
+    pub ca_cert: Option<PathBuf>,
+    /// Client certificate, with `client_key` in PKCS#8
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Queries {
+    pub temperature: String,
+    pub temperature_max: String,
+    pub temperature_min: String,
+    pub wind_speed: String,
+    pub humidity: String,
+    pub cloud_cover: String,
+    pub weather_code: String,
+    pub battery_soc: String,
+    pub avg_solar_1h: String,
+    pub avg_power_1h: String,
+}
+
+impl Default for Queries {
+    fn default() -> Self {
+        Queries {
+            temperature: "last_over_time(temperature_celsius{forecast=\"current\"}[2h])"
+                .to_string(),
+            temperature_max: "last_over_time(temperature_celsius_maximum{forecast=\"0d\"}[2h])"
+                .to_string(),
+            temperature_min: "last_over_time(temperature_celsius_minimum{forecast=\"0d\"}[2h])"
+                .to_string(),
+            wind_speed: "last_over_time(windspeed_kmph{forecast=\"current\"}[1h])".to_string(),
+            humidity: "last_over_time(humidity_percentage{forecast=\"current\"}[2h])".to_string(),
+            cloud_cover: "last_over_time(cloudcover_percentage{forecast=\"current\"}[2h])"
+                .to_string(),
+            weather_code: "last_over_time(weather_code{forecast=\"current\"}[2h])".to_string(),
+            battery_soc: "imeon_battery_soc".to_string(),
+            avg_solar_1h: "avg_over_time(imeon_pv_input_power1[1h])".to_string(),
+            avg_power_1h: "avg_over_time(imeon_em_power[1h])".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Decimals {
+    pub temperature: u32,
+    pub temperature_max: u32,
+    pub temperature_min: u32,
+    pub wind_speed: u32,
+    pub humidity: u32,
+    pub cloud_cover: u32,
+    pub battery_soc: u32,
+    pub avg_solar_1h: u32,
+    pub avg_power_1h: u32,
+}
+
+/// Range queries telling how a value evolved, an empty query disables its trend
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Trends {
+    /// Seconds before the announcement covered by the trends
+    pub window: u64,
+    /// Seconds between two samples of the range queries
+    pub step: u64,
+    pub temperature: String,
+    pub battery_soc: String,
+}
+
+impl Default for Trends {
+    fn default() -> Self {
+        Trends {
+            window: 6 * 3600,
+            step: 300,
+            temperature: "temperature_celsius{forecast=\"current\"}".to_string(),
+            battery_soc: "imeon_battery_soc".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Sources {
+    pub prometheus: PrometheusSource,
+    /// Other sources, by the name given in the `source` of the sections
+    #[serde(flatten)]
+    pub others: BTreeMap<String, SourceConfig>,
+}
+
+/// Language model server and the generation settings of the rewrite, unset
+/// options keep the default of the model
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Llm {
+    /// API of the server, `none` disables the rewrite
+    pub backend: BackendKind,
+    /// Server with its port, `https://` for a server behind a TLS proxy. For
+    /// OpenAI-compatible servers, the base URL without `/v1`
+    pub url: String,
+    /// Bearer token of OpenAI-compatible servers
+    pub api_key: Option<Secret>,
+    pub model: String,
+    /// System prompt sent with the message
+    pub system: Option<String>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    /// Maximum number of tokens of the rewrite
+    pub num_predict: Option<i32>,
+    /// Size of the context window in tokens, Ollama only
+    pub num_ctx: Option<u32>,
+    /// Fixed seed for reproducible rewrites
+    pub seed: Option<i32>,
+    /// How long the model stays loaded: `-1`, `0` or e.g. `5m`, Ollama only
+    pub keep_alive: Option<String>,
+    /// Wall-clock budget of the rewrite in seconds, model loading included
+    pub timeout: u64,
+    /// What to send when the budget is exceeded
+    pub on_timeout: OnTimeout,
+    /// Handlebars file replacing the embedded rewrite prompt
+    pub prompt: Option<PathBuf>,
+    /// Tone asked to the model
+    pub tone: String,
+    /// Maximum length of the rewrite in characters, as asked to the model
+    pub max_length: usize,
+    /// Few-shot examples replacing the ones of the catalog
+    pub examples: Vec<Example>,
+}
+
+impl Default for Llm {
+    fn default() -> Self {
+        Llm {
+            backend: BackendKind::default(),
+            url: "http://127.0.0.1:11434".to_string(),
+            api_key: None,
+            model: String::new(),
+            system: None,
+            temperature: None,
+            top_p: None,
+            num_predict: None,
+            num_ctx: None,
+            seed: None,
+            keep_alive: None,
+            timeout: 60,
+            on_timeout: OnTimeout::default(),
+            prompt: None,
+            tone: "warm, cheerful and concise".to_string(),
+            max_length: 400,
+            examples: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Phrases {
+    /// Directory of TOML, YAML or JSON phrase files replacing the embedded catalog
+    /// categories: `<locale>.*` files, then the files of a `<locale>` subdirectory
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplatesDir {
+    /// Directory of `<name>.hbs` Handlebars templates replacing or adding to the embedded ones
+    pub dir: Option<PathBuf>,
+}
+
+/// How the announcements are delivered to the sinks, durations are in seconds
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeliveryConfig {
+    /// Timeout of each request to a sink
+    pub timeout: u64,
+    /// Attempts after the first failure before the announcement goes to the outbox
+    pub retries: u32,
+    /// Wait before the first retry, doubled on each following one
+    pub backoff: u64,
+    /// Upper bound of the wait between two retries
+    pub max_backoff: u64,
+    /// Announcements of the outbox older than this are dropped, 0 disables the outbox
+    pub max_age: u64,
+    /// JSON-lines file of the announcements waiting for a sink
+    pub outbox: PathBuf,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        DeliveryConfig {
+            timeout: 10,
+            retries: 3,
+            backoff: 1,
+            max_backoff: 30,
+            max_age: 900,
+            outbox: state_dir().join("outbox.jsonl"),
+        }
+    }
+}
+
+/// What is remembered between runs: messages sent and alert states
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateConfig {
+    /// JSON file of the state
+    pub path: PathBuf,
+    /// Days a sent message stays in the history
+    pub history_days: u64,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        StateConfig {
+            path: state_dir().join("state.json"),
+            history_days: 30,
+        }
+    }
+}
+
+/// HTTP API of the `serve` command
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address and port to listen on
+    pub listen: SocketAddr,
+    /// Token expected as `Authorization: Bearer <token>`, except by `/health`
+    pub token: Option<Secret>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            listen: SocketAddr::from(([127, 0, 0, 1], 8088)),
+            token: None,
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/notifier`, or `~/.local/state/notifier`
+pub fn state_dir() -> PathBuf {
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(".local/state"),
+    };
+    base.join("notifier")
+}
+
+/// Alert mode: rules checked on each tick and notified apart from the
+/// announcements, durations are in seconds
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    /// Time between two checks in the daemon, 0 leaves them to the `alerts` command
+    pub interval: u64,
+    /// Time before an alert still active is sent again, 0 sends it once
+    pub renotify: u64,
+    pub rules: Vec<RuleConfig>,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        AlertsConfig {
+            interval: 300,
+            renotify: 6 * 3600,
+            rules: alerts::default_rules(),
+        }
+    }
+}
+
+/// Values above or below which the composers add a warning
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    /// Wind speed in km/h above which a storm warning is added
+    pub strong_wind: f32,
+    /// Minimum temperature in °C below which a frost warning is added
+    pub cold_temperature: f32,
+    /// Maximum temperature in °C above which a heat warning is added
+    pub high_temperature: f32,
+    /// Battery state of charge in % from which the battery is reported full
+    pub battery_full: f32,
+    /// Battery state of charge in % below which the `battery_low` alert starts
+    pub battery_low: f32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            strong_wind: 40.0,
+            cold_temperature: 5.0,
+            high_temperature: 27.0,
+            battery_full: 100.0,
+            battery_low: 20.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Language of the announcements
+    pub locale: Locale,
+    pub sources: Sources,
+    /// Parts of the announcement, in order
+    pub sections: Vec<SectionConfig>,
+    /// Every sink receives each announcement
+    pub sinks: Vec<SinkConfig>,
+    pub delivery: DeliveryConfig,
+    pub state: StateConfig,
+    pub llm: Llm,
+    pub phrases: Phrases,
+    pub templates: TemplatesDir,
+    pub thresholds: Thresholds,
+    pub alerts: AlertsConfig,
+    pub server: ServerConfig,
+    pub schedule: Vec<Slot>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            locale: Locale::default(),
+            sources: Sources::default(),
+            sections: sections::default_sections(),
+            sinks: vec![SinkConfig::Notifyd {
+                name: None,
+                url: "http://127.0.0.1:8080".to_string(),
+            }],
+            delivery: DeliveryConfig::default(),
+            state: StateConfig::default(),
+            llm: Llm::default(),
+            phrases: Phrases::default(),
+            templates: TemplatesDir::default(),
+            thresholds: Thresholds::default(),
+            alerts: AlertsConfig::default(),
+            server: ServerConfig::default(),
+            schedule: schedule::DEFAULT_SLOTS
+                .split(',')
+                .map(|s| s.parse().expect("Valid default slot"))
+                .collect(),
+        }
+    }
+}
+
+fn check_url(name: &str, url: &str) -> Result<(), String> {
+    reqwest::Url::parse(url)
+        .map(|_| ())
+        .map_err(|e| format!("{}: invalid URL '{}': {}", name, url, e))
+}
+
+const REDACTED: &str = "<redacted>";
+
+fn redact(value: &mut toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                match value {
+                    // Usually an authorization or an API key
+                    toml::Value::Table(headers) if key == "headers" => {
+                        for (_, header) in headers.iter_mut() {
+                            *header = toml::Value::String(REDACTED.to_string());
+                        }
+                    }
+                    _ => redact(value),
+                }
+            }
+        }
+        toml::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+impl Config {
+    /// Load the configuration file, missing keys take their default value
+    pub fn load(path: &Path) -> Result<Config, Box<dyn error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let config: Config =
+            toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    /// Configuration as TOML, with the header values replaced by a placeholder.
+    /// The secrets are kept as they are `{ file = … }` or `{ env = … }`
+    /// references, naming where the secret is.
+    pub fn redacted(&self) -> Result<String, Box<dyn error::Error>> {
+        let mut value = toml::Value::try_from(self)?;
+        redact(&mut value);
+        Ok(toml::to_string_pretty(&value)?)
+    }
+
+    /// Check the resolved configuration before using it
+    pub fn validate(&self) -> Result<(), Box<dyn error::Error>> {
+        let prometheus = &self.sources.prometheus;
+        check_url("sources.prometheus.url", &prometheus.url)?;
+        if prometheus.timeout == 0 || prometheus.budget == 0 {
+            return Err("sources.prometheus: timeout and budget must be at least 1 second".into());
+        }
+        if prometheus.trends.window < 3600 || prometheus.trends.step == 0 {
+            return Err(
+                "sources.prometheus.trends: window must be at least 3600 seconds and step at least 1"
+                    .into(),
+            );
+        }
+        if prometheus.username.is_some() != prometheus.password.is_some() {
+            return Err("sources.prometheus: username and password go together".into());
+        }
+        if prometheus.password.is_some() && prometheus.bearer_token.is_some() {
+            return Err("sources.prometheus: set either password or bearer_token".into());
+        }
+        for (name, value) in prometheus.headers.iter() {
+            reqwest::header::HeaderName::from_str(name)
+                .map_err(|e| format!("sources.prometheus.headers: '{}': {}", name, e))?;
+            reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| format!("sources.prometheus.headers.{}: {}", name, e))?;
+        }
+        if prometheus.tls.client_cert.is_some() != prometheus.tls.client_key.is_some() {
+            return Err("sources.prometheus.tls: client_cert and client_key go together".into());
+        }
+        for (name, source) in self.sources.others.iter() {
+            source
+                .validate()
+                .map_err(|e| format!("sources.{}: {}", name, e))?;
+        }
+        let mut names = std::collections::HashSet::new();
+        for section in self.sections.iter() {
+            section.validate().map_err(|e| format!("sections: {}", e))?;
+            if section.source != "prometheus" && !self.sources.others.contains_key(&section.source)
+            {
+                return Err(format!("sections: unknown source '{}'", section.source).into());
+            }
+            let name = section.resolve(self).name;
+            if !names.insert(name.clone()) {
+                return Err(format!("sections: several sections are named '{}'", name).into());
+            }
+        }
+        if self.sinks.is_empty() {
+            return Err("sinks: at least one sink is required".into());
+        }
+        let mut names = std::collections::HashSet::new();
+        for sink in self.sinks.iter() {
+            match sink {
+                SinkConfig::Notifyd { url, .. } => check_url("sinks.url", url)?,
+                SinkConfig::Email {
+                    username, password, ..
+                } if username.is_some() != password.is_some() => {
+                    return Err(
+                        format!("sink {}: username and password go together", sink.name()).into(),
+                    );
+                }
+                _ => {}
+            }
+            // The outbox finds the sink of a pending announcement by its name
+            if !names.insert(sink.name()) {
+                return Err(format!(
+                    "sinks: several sinks are named '{}', set a distinct name",
+                    sink.name()
+                )
+                .into());
+            }
+        }
+        if self.delivery.timeout == 0 {
+            return Err("delivery.timeout: must be at least 1 second".into());
+        }
+        match self.llm.backend {
+            BackendKind::Ollama => {
+                llm::endpoint(&self.llm.url).map_err(|e| format!("llm.url: {}", e))?;
+                if let Some(value) = &self.llm.keep_alive {
+                    llm::keep_alive(value).map_err(|e| format!("llm.keep_alive: {}", e))?;
+                }
+            }
+            BackendKind::Openai => check_url("llm.url", &self.llm.url)?,
+            BackendKind::None => {}
+        }
+        if self.llm.backend != BackendKind::None && self.llm.model.is_empty() {
+            return Err("llm.model: no model set".into());
+        }
+        if self.llm.timeout == 0 {
+            return Err("llm.timeout: must be at least 1 second".into());
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for rule in self.alerts.rules.iter() {
+            let rule = rule.resolve(self);
+            rule.validate()
+                .map_err(|e| format!("alerts.rules: {}", e))?;
+            if rule.source != "prometheus" && !self.sources.others.contains_key(&rule.source) {
+                return Err(format!("alerts.rules: unknown source '{}'", rule.source).into());
+            }
+            if !names.insert(rule.name.clone()) {
+                return Err(
+                    format!("alerts.rules: several rules are named '{}'", rule.name).into(),
+                );
+            }
+        }
+
+        if self.schedule.is_empty() {
+            return Err("schedule: at least one slot is required".into());
+        }
+
+        let t = &self.thresholds;
+        if t.cold_temperature >= t.high_temperature {
+            return Err(format!(
+                "thresholds: cold_temperature ({}) must be below high_temperature ({})",
+                t.cold_temperature, t.high_temperature
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_secrets() {
+        let config: Config = toml::from_str(
+            r#"
+            [sources.prometheus]
+            headers = { "X-Scope-OrgID" = "tenant-a" }
+            bearer_token = { env = "PROMETHEUS_TOKEN" }
+            [[sinks]]
+            type = "webhook"
+            url = "http://127.0.0.1:9000"
+            headers = { Authorization = "Bearer hunter2" }
+            [[sinks]]
+            type = "matrix"
+            homeserver = "https://matrix.org"
+            room_id = "!room:matrix.org"
+            access_token = { env = "MATRIX_TOKEN" }
+            "#,
+        )
+        .unwrap();
+
+        let redacted = config.redacted().unwrap();
+        assert!(!redacted.contains("hunter2"), "{}", redacted);
+        assert!(!redacted.contains("tenant-a"), "{}", redacted);
+        assert!(redacted.contains("PROMETHEUS_TOKEN"), "{}", redacted);
+        assert!(redacted.contains("MATRIX_TOKEN"), "{}", redacted);
+        let parsed: toml::Value = toml::from_str(&redacted).unwrap();
+        assert_eq!(
+            parsed["sinks"][0]["headers"]["Authorization"].as_str(),
+            Some(REDACTED)
+        );
+    }
+
+    #[test]
+    fn email_credentials_together() {
+        let config: Config = toml::from_str(
+            r#"
+            [[sinks]]
+            type = "email"
+            host = "smtp.example.org"
+            username = "notifier"
+            from = "notifier@example.org"
+            to = ["home@example.org"]
+            "#,
+        )
+        .unwrap();
+
+        let error = config.validate().unwrap_err().to_string();
+        assert_eq!(error, "sink email: username and password go together");
+    }
+}
@@ -0,0 +1,507 @@
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::io::{BufRead, Write};
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::DeliveryConfig;
+use crate::sinks::{Message, Notifier, SinkError};
+
+/// Announcement waiting in the outbox for a sink that was unreachable
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Pending {
+    pub created_at: DateTime<Utc>,
+    pub sink: String,
+    pub text: String,
+}
+
+/// JSON-lines file holding the announcements not delivered yet
+pub struct Outbox {
+    path: PathBuf,
+}
+
+impl Outbox {
+    pub fn new(path: PathBuf) -> Outbox {
+        Outbox { path }
+    }
+
+    /// Exclusive lock on the outbox, shared with the other processes using it
+    /// and released when the file is dropped
+    fn lock(&self) -> Result<std::fs::File, Box<dyn error::Error>> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let path = self.path.with_extension("lock");
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        // SAFETY: the descriptor stays open for the duration of the call
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            let e = std::io::Error::last_os_error();
+            return Err(format!("Cannot lock {}: {}", path.display(), e).into());
+        }
+        Ok(file)
+    }
+
+    pub fn load(&self) -> Result<Vec<Pending>, Box<dyn error::Error>> {
+        let _lock = self.lock()?;
+        self.read()
+    }
+
+    fn read(&self) -> Result<Vec<Pending>, Box<dyn error::Error>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Cannot read {}: {}", self.path.display(), e).into()),
+        };
+
+        let mut pending = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(p) => pending.push(p),
+                Err(e) => warn!("Outbox {}: skipping entry: {}", self.path.display(), e),
+            }
+        }
+        Ok(pending)
+    }
+
+    pub fn push(&self, pending: &Pending) -> Result<(), Box<dyn error::Error>> {
+        let _lock = self.lock()?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Cannot write {}: {}", self.path.display(), e))?;
+        writeln!(file, "{}", serde_json::to_string(pending)?)?;
+        Ok(())
+    }
+
+    /// Take `done` out of the outbox, keeping what was added since it was loaded
+    pub fn remove(&self, done: &[Pending]) -> Result<(), Box<dyn error::Error>> {
+        let _lock = self.lock()?;
+        let mut pending = self.read()?;
+        pending.retain(|p| !done.contains(p));
+
+        if pending.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+
+        let tmp = self.path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp)
+            .map_err(|e| format!("Cannot write {}: {}", tmp.display(), e))?;
+        for p in pending.iter() {
+            writeln!(file, "{}", serde_json::to_string(p)?)?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Outcome of sending a message to a sink
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SinkResult {
+    pub sink: String,
+    /// Last error, the message then being in the outbox when it is enabled
+    pub error: Option<String>,
+}
+
+/// Sinks with the retry policy and the outbox
+pub struct Delivery {
+    sinks: Vec<Box<dyn Notifier>>,
+    config: DeliveryConfig,
+    outbox: Outbox,
+}
+
+impl Delivery {
+    pub fn new(sinks: Vec<Box<dyn Notifier>>, config: DeliveryConfig) -> Delivery {
+        let outbox = Outbox::new(config.outbox.clone());
+        Delivery {
+            sinks,
+            config,
+            outbox,
+        }
+    }
+
+    /// Try a sink until it succeeds or the retries are exhausted, doubling the wait each time
+    async fn notify_with_retry(
+        &self,
+        sink: &dyn Notifier,
+        message: &Message<'_>,
+    ) -> Result<(), SinkError> {
+        let mut backoff = Duration::from_secs(self.config.backoff);
+        let max_backoff = Duration::from_secs(self.config.max_backoff);
+        let mut attempt = 0;
+
+        loop {
+            match sink.notify(message).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.config.retries => return Err(e),
+                Err(e) => {
+                    attempt += 1;
+                    warn!(
+                        "Sink {}: {}, retry {}/{} in {}s",
+                        sink.name(),
+                        e,
+                        attempt,
+                        self.config.retries,
+                        backoff.as_secs()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(max_backoff);
+                }
+            }
+        }
+    }
+
+    /// Send `text` to every sink at once, a failing sink does not stop the others
+    /// and its copy is kept in the outbox. Returns the result of each sink.
+    pub async fn send(&self, text: &str) -> Vec<SinkResult> {
+        let message = Message {
+            text,
+            created_at: Utc::now(),
+        };
+        let results = futures::future::join_all(
+            self.sinks
+                .iter()
+                .map(|s| self.notify_with_retry(s.as_ref(), &message)),
+        )
+        .await;
+
+        let mut delivered = Vec::new();
+        for (sink, result) in self.sinks.iter().zip(results) {
+            let error = match result {
+                Ok(()) => None,
+                Err(e) => {
+                    warn!("Sink {}: {}", sink.name(), e);
+                    self.keep(sink.name(), &message);
+                    Some(e.to_string())
+                }
+            };
+            delivered.push(SinkResult {
+                sink: sink.name().to_string(),
+                error,
+            });
+        }
+        delivered
+    }
+
+    fn keep(&self, sink: &str, message: &Message<'_>) {
+        if self.config.max_age == 0 {
+            return;
+        }
+
+        let pending = Pending {
+            created_at: message.created_at,
+            sink: sink.to_string(),
+            text: message.text.to_string(),
+        };
+        match self.outbox.push(&pending) {
+            Ok(()) => info!("Sink {}: kept in the outbox", sink),
+            Err(e) => warn!("Sink {}: cannot keep the message: {}", sink, e),
+        }
+    }
+
+    /// Retry the announcements of the outbox, the ones older than `max_age` are
+    /// dropped. The sinks are retried at once, each one stopping at its first
+    /// failure so a sink still down costs a single timeout. The sinks in `down`
+    /// just failed and are left alone.
+    pub async fn flush_outbox(&self, down: &[&str]) -> Result<(), Box<dyn error::Error>> {
+        let pending = self.outbox.load()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let max_age = chrono::Duration::seconds(self.config.max_age as i64);
+        // Entries leaving the outbox, delivered or dropped
+        let mut done = Vec::new();
+        let mut queued = Vec::new();
+
+        for p in pending {
+            if Utc::now() - p.created_at > max_age {
+                warn!(
+                    "Sink {}: dropping announcement from {}, too old",
+                    p.sink, p.created_at
+                );
+                done.push(p);
+            } else if !self.sinks.iter().any(|s| s.name() == p.sink) {
+                warn!(
+                    "Sink {}: no longer configured, dropping announcement",
+                    p.sink
+                );
+                done.push(p);
+            } else {
+                queued.push(p);
+            }
+        }
+
+        let up = self.sinks.iter().filter(|s| !down.contains(&s.name()));
+        let delivered = futures::future::join_all(up.map(|sink| {
+            let queued = queued.iter().filter(|p| p.sink == sink.name());
+            Self::flush_sink(sink.as_ref(), queued)
+        }))
+        .await;
+        done.extend(delivered.into_iter().flatten());
+
+        self.outbox.remove(&done)
+    }
+
+    /// Send the announcements of a sink in order until one fails, returning the
+    /// delivered ones
+    async fn flush_sink<'a>(
+        sink: &dyn Notifier,
+        queued: impl Iterator<Item = &'a Pending>,
+    ) -> Vec<Pending> {
+        let mut delivered = Vec::new();
+        for p in queued {
+            let message = Message {
+                text: &p.text,
+                created_at: p.created_at,
+            };
+            match sink.notify(&message).await {
+                Ok(()) => {
+                    info!(
+                        "Sink {}: delivered announcement from {}",
+                        p.sink, p.created_at
+                    );
+                    delivered.push(p.clone());
+                }
+                Err(e) => {
+                    warn!("Sink {}: still failing: {}", p.sink, e);
+                    break;
+                }
+            }
+        }
+        delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::time::Instant;
+
+    /// What a stub sink went through
+    #[derive(Default)]
+    struct Log {
+        /// Calls failing before the sink works again
+        failures: AtomicU32,
+        /// Time, message id and text of each call
+        calls: Mutex<Vec<(Instant, String, String)>>,
+    }
+
+    struct Stub(Arc<Log>);
+
+    #[async_trait]
+    impl Notifier for Stub {
+        fn name(&self) -> &str {
+            "stub"
+        }
+
+        async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+            self.0.calls.lock().unwrap().push((
+                Instant::now(),
+                message.id(),
+                message.text.to_string(),
+            ));
+            let failing = self
+                .0
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |f| f.checked_sub(1))
+                .is_ok();
+            if failing {
+                return Err("unreachable".into());
+            }
+            Ok(())
+        }
+    }
+
+    /// Sink queueing another announcement while it is called, as a
+    /// process sharing the outbox would
+    struct Appender(Outbox);
+
+    #[async_trait]
+    impl Notifier for Appender {
+        fn name(&self) -> &str {
+            "appender"
+        }
+
+        async fn notify(&self, _: &Message<'_>) -> Result<(), SinkError> {
+            self.0
+                .push(&pending("appender", 5))
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+
+    fn delivery(name: &str, failures: u32, config: DeliveryConfig) -> (Delivery, Arc<Log>) {
+        let log = Arc::new(Log::default());
+        log.failures.store(failures, Ordering::SeqCst);
+        let config = DeliveryConfig {
+            outbox: std::env::temp_dir().join(format!(
+                "notifier-outbox-{}-{}.jsonl",
+                name,
+                std::process::id()
+            )),
+            ..config
+        };
+        let _ = std::fs::remove_file(&config.outbox);
+        let delivery = Delivery::new(vec![Box::new(Stub(log.clone()))], config);
+        (delivery, log)
+    }
+
+    fn pending(sink: &str, age: i64) -> Pending {
+        Pending {
+            created_at: Utc::now() - chrono::Duration::seconds(age),
+            sink: sink.to_string(),
+            text: format!("{} seconds ago", age),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_with_capped_backoff() {
+        let config = DeliveryConfig {
+            retries: 4,
+            backoff: 1,
+            max_backoff: 3,
+            max_age: 0,
+            ..DeliveryConfig::default()
+        };
+        let (delivery, log) = delivery("retries", u32::MAX, config);
+
+        let results = delivery.send("Bonjour").await;
+        assert_eq!(results[0].sink, "stub");
+        assert_eq!(results[0].error.as_deref(), Some("unreachable"));
+
+        let calls = log.calls.lock().unwrap();
+        let waits: Vec<u64> = calls
+            .windows(2)
+            .map(|w| (w[1].0 - w[0].0).as_secs())
+            .collect();
+        assert_eq!(waits, vec![1, 2, 3, 3]);
+        // Without the outbox nothing is kept
+        assert!(!delivery.config.outbox.exists());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn outbox_flushed_later() {
+        let config = DeliveryConfig {
+            retries: 1,
+            ..DeliveryConfig::default()
+        };
+        let (delivery, log) = delivery("flush", 2, config);
+
+        let results = delivery.send("Bonjour").await;
+        assert!(results[0].error.is_some());
+        assert_eq!(delivery.outbox.load().unwrap().len(), 1);
+
+        delivery.flush_outbox(&[]).await.unwrap();
+        assert!(!delivery.config.outbox.exists());
+
+        let calls = log.calls.lock().unwrap();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[2].2, "Bonjour");
+        // Same message for the sink on every attempt
+        assert!(calls.iter().all(|c| c.1 == calls[0].1));
+    }
+
+    #[tokio::test]
+    async fn outbox_drops_old_and_unknown() {
+        let config = DeliveryConfig {
+            max_age: 60,
+            ..DeliveryConfig::default()
+        };
+        let (delivery, log) = delivery("drop", 1, config);
+        for p in [
+            pending("stub", 3600),
+            pending("gone", 10),
+            pending("stub", 10),
+        ] {
+            delivery.outbox.push(&p).unwrap();
+        }
+
+        // Still failing, only the recent entry of a configured sink stays
+        delivery.flush_outbox(&[]).await.unwrap();
+        let remaining = delivery.outbox.load().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].text, "10 seconds ago");
+        assert_eq!(remaining[0].sink, "stub");
+
+        delivery.flush_outbox(&[]).await.unwrap();
+        assert!(!delivery.config.outbox.exists());
+        let texts: Vec<String> = log
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| c.2.clone())
+            .collect();
+        assert_eq!(texts, vec!["10 seconds ago", "10 seconds ago"]);
+    }
+
+    #[tokio::test]
+    async fn outbox_keeps_entries_added_while_flushing() {
+        let config = DeliveryConfig {
+            outbox: std::env::temp_dir().join(format!(
+                "notifier-outbox-concurrent-{}.jsonl",
+                std::process::id()
+            )),
+            ..DeliveryConfig::default()
+        };
+        let _ = std::fs::remove_file(&config.outbox);
+        let appender = Appender(Outbox::new(config.outbox.clone()));
+        let delivery = Delivery::new(vec![Box::new(appender)], config);
+        delivery.outbox.push(&pending("appender", 10)).unwrap();
+
+        delivery.flush_outbox(&[]).await.unwrap();
+        let remaining = delivery.outbox.load().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].text, "5 seconds ago");
+    }
+
+    #[tokio::test]
+    async fn outbox_stops_at_a_failing_sink() {
+        let config = DeliveryConfig {
+            max_age: 60,
+            ..DeliveryConfig::default()
+        };
+        let (delivery, log) = delivery("stop", 1, config);
+        for age in [30, 20, 10] {
+            delivery.outbox.push(&pending("stub", age)).unwrap();
+        }
+
+        // The sink is tried once and everything stays in order
+        delivery.flush_outbox(&[]).await.unwrap();
+        assert_eq!(log.calls.lock().unwrap().len(), 1);
+        let texts: Vec<String> = delivery
+            .outbox
+            .load()
+            .unwrap()
+            .into_iter()
+            .map(|p| p.text)
+            .collect();
+        assert_eq!(
+            texts,
+            vec!["30 seconds ago", "20 seconds ago", "10 seconds ago"]
+        );
+
+        delivery.flush_outbox(&[]).await.unwrap();
+        assert!(!delivery.config.outbox.exists());
+        assert_eq!(log.calls.lock().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,528 @@
+//! Spoken announcements of the house: metrics read from Prometheus and other
+//! sources, composed with the phrases of a catalog and the templates,
+//! optionally rewritten by a language model, then sent to the sinks.
+//!
+//! [`Context::new`] builds everything a run needs from a [`Config`];
+//! [`compose`] renders an announcement without sending it, [`announce`]
+//! also rewrites, sends and records it.
+//!
+//! Nothing is printed here: results are returned, in a [`state::Record`] for
+//! what was sent, and diagnostics go through the `log` crate.
+
+use chrono::prelude::*;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod alerts;
+pub mod catalog;
+pub mod clock;
+pub mod config;
+pub mod delivery;
+pub mod llm;
+pub mod locale;
+pub mod metrics;
+pub mod schedule;
+pub mod sections;
+pub mod server;
+pub mod sinks;
+pub mod sources;
+pub mod state;
+pub mod templates;
+
+pub use catalog::{Catalog, Category, Pick, Text};
+pub use clock::{Clock, FixedClock, SystemClock};
+pub use config::Config;
+pub use delivery::Delivery;
+pub use llm::Rewriter;
+pub use locale::Locale;
+pub use metrics::Metrics;
+pub use sinks::Notifier;
+pub use sources::MetricSource;
+
+use schedule::Schedule;
+use sections::SectionConfig;
+use sources::Prometheus;
+use state::{Record, RecordKind, StateStore};
+use templates::{Templates, DEFAULT_TEMPLATE};
+
+/// Clients shared by every announcement of a run
+pub struct Context {
+    pub metrics: Metrics,
+    pub delivery: Delivery,
+    pub rewriter: Rewriter,
+    pub config: Config,
+    /// Sections of the configuration, presets resolved
+    pub sections: Vec<SectionConfig>,
+    pub store: StateStore,
+    pub catalog: Arc<Catalog>,
+    pub templates: Templates,
+    pub clock: Box<dyn Clock>,
+}
+
+/// Metric sources of the configuration, by name
+pub fn build_sources(
+    config: &Config,
+) -> Result<HashMap<String, Box<dyn MetricSource>>, Box<dyn error::Error>> {
+    let mut sources: HashMap<String, Box<dyn MetricSource>> = HashMap::new();
+    sources.insert(
+        "prometheus".to_string(),
+        Box::new(Prometheus::new(
+            metrics::client(&config.sources.prometheus)
+                .map_err(|e| format!("sources.prometheus: {}", e))?,
+        )),
+    );
+    for (name, source) in config.sources.others.iter() {
+        sources.insert(
+            name.clone(),
+            source
+                .build()
+                .map_err(|e| format!("sources.{}: {}", name, e))?,
+        );
+    }
+    Ok(sources)
+}
+
+impl Context {
+    /// Load the catalog and templates and build the clients of a run
+    pub fn new(
+        config: Config,
+        sources: HashMap<String, Box<dyn MetricSource>>,
+        clock: Box<dyn Clock>,
+    ) -> Result<Context, Box<dyn error::Error>> {
+        let catalog = Arc::new(match &config.phrases.dir {
+            Some(dir) => Catalog::load(config.locale, dir)?,
+            None => Catalog::embedded(config.locale),
+        });
+        let mut templates = Templates::new(catalog.clone(), config.templates.dir.as_deref())?;
+        let sections: Vec<SectionConfig> =
+            config.sections.iter().map(|s| s.resolve(&config)).collect();
+        for section in sections.iter() {
+            if !section.template.is_empty() {
+                templates.add(&section.template_name(), &section.template)?;
+            } else if !templates.has(&section.name) {
+                return Err(format!(
+                    "sections: no template '{}', set template or add {}.hbs",
+                    section.name, section.name
+                )
+                .into());
+            }
+        }
+        for slot in config.schedule.iter() {
+            if let Some(name) = &slot.template {
+                if !templates.has(name) {
+                    return Err(format!("schedule: unknown template '{}'", name).into());
+                }
+            }
+        }
+
+        let timeout = Duration::from_secs(config.delivery.timeout);
+        let http = reqwest::Client::builder().timeout(timeout).build()?;
+        let sinks = config
+            .sinks
+            .iter()
+            .map(|s| s.build(&http, timeout))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let prometheus = &config.sources.prometheus;
+        let metrics = Metrics::new(
+            sources,
+            match prometheus.max_age {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            },
+            Duration::from_secs(prometheus.budget),
+        );
+
+        Ok(Context {
+            metrics,
+            delivery: Delivery::new(sinks, config.delivery.clone()),
+            rewriter: Rewriter::new(&config.llm, config.locale, &catalog)?,
+            store: StateStore::new(config.state.path.clone(), config.state.history_days),
+            config,
+            sections,
+            catalog,
+            templates,
+            clock,
+        })
+    }
+}
+
+/// Everything a message template can refer to
+#[derive(Serialize)]
+struct MessageValues {
+    date: String,
+    time: String,
+    weekday: String,
+    day: u32,
+    month: String,
+    month_number: u32,
+    year: i32,
+    hour: u32,
+    minute: u32,
+    /// Hours covered by the trends
+    trend_hours: u64,
+    /// Rendered sections, in order
+    sections: Vec<String>,
+    /// Variables of every section
+    #[serde(flatten)]
+    values: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Announcement with the metric values it was made of
+pub struct Composed {
+    pub text: String,
+    /// Rendered sections, in the order of the configuration
+    pub sections: Vec<String>,
+    /// Rounded values, by metric name
+    pub values: BTreeMap<String, f64>,
+}
+
+/// Read the metrics and render `template` for `time`
+pub async fn compose(
+    ctx: &Context,
+    time: DateTime<Local>,
+    template: &str,
+) -> Result<Composed, Box<dyn error::Error>> {
+    let catalog = &ctx.catalog;
+    let trends = &ctx.config.sources.prometheus.trends;
+    let mut all = serde_json::Map::new();
+
+    let queries: Vec<(&str, &str)> = ctx.sections.iter().flat_map(|s| s.queries()).collect();
+    let trend_queries: Vec<(&str, &str)> = ctx.sections.iter().flat_map(|s| s.trends()).collect();
+    let readings = ctx
+        .metrics
+        .fetch(
+            &queries,
+            &trend_queries,
+            Duration::from_secs(trends.window),
+            Duration::from_secs(trends.step),
+        )
+        .await;
+
+    let mut values = MessageValues {
+        date: catalog.text(
+            Text::Date,
+            &[
+                ("weekday", catalog.weekday(time.weekday())),
+                ("day", &time.day().to_string()),
+                ("month", catalog.month(time.month())),
+            ],
+        ),
+        time: catalog.time(time.hour(), time.minute()),
+        weekday: catalog.weekday(time.weekday()).to_string(),
+        day: time.day(),
+        month: catalog.month(time.month()).to_string(),
+        month_number: time.month(),
+        year: time.year(),
+        hour: time.hour(),
+        minute: time.minute(),
+        trend_hours: (trends.window as f64 / 3600.0).round() as u64,
+        sections: Vec::new(),
+        values: serde_json::Map::new(),
+    };
+
+    // Each section sees the date variables and its own ones
+    for section in ctx.sections.iter() {
+        let (section_values, phrases) = section.values(&readings, catalog);
+        values.values = section_values;
+        let mut text = ctx.templates.render(&section.template_name(), &values)?;
+        for phrase in phrases {
+            text.push(' ');
+            text.push_str(&phrase);
+        }
+        values.sections.push(text);
+        all.extend(values.values.clone());
+    }
+    values.values = all;
+    let sections = values.sections.clone();
+
+    let used = ctx
+        .sections
+        .iter()
+        .flat_map(|s| s.metrics.iter().map(move |m| (s, m)))
+        .filter_map(|(s, m)| {
+            let value = readings.get(&s.source, &m.query)?;
+            Some((m.name.clone(), value.rounded(m.decimals)))
+        })
+        .collect();
+
+    Ok(Composed {
+        text: ctx.templates.render(template, &values)?,
+        sections,
+        values: used,
+    })
+}
+
+/// Compose, rewrite and send one announcement, `None` when it was dropped
+/// as `[llm] on_timeout = "drop"` asks
+pub async fn announce(
+    ctx: &Context,
+    time: DateTime<Local>,
+    template: &str,
+) -> Result<Option<Record>, Box<dyn error::Error>> {
+    // A state that cannot be read is left untouched
+    let state = match ctx.store.load() {
+        Ok(state) => {
+            ctx.catalog.set_usage(state.phrases.clone());
+            Some(state)
+        }
+        Err(e) => {
+            warn!("State: {}", e);
+            None
+        }
+    };
+    ctx.catalog.take_picks();
+    let composed = compose(ctx, time, template).await?;
+    let phrases = ctx.catalog.take_picks();
+    let mut message = composed.text;
+
+    if let Some(backend) = ctx.rewriter.backend() {
+        match ctx.rewriter.rewrite(&message).await {
+            Ok(Some(rewritten)) => {
+                info!(
+                    "Did run {} model {} on the message",
+                    backend, ctx.config.llm.model
+                );
+                message = rewritten;
+            }
+            Ok(None) => return Ok(None),
+            Err(e) => warn!("Rewrite: {}, keeping the original message", e),
+        }
+    }
+
+    let deliveries = ctx.delivery.send(&message).await;
+    // Announcements kept while a sink was down go out after the new one, which
+    // stays on time
+    let down: Vec<&str> = deliveries
+        .iter()
+        .filter(|d| d.error.is_some())
+        .map(|d| d.sink.as_str())
+        .collect();
+    if let Err(e) = ctx.delivery.flush_outbox(&down).await {
+        warn!("Outbox: {}", e);
+    }
+    let delivered = deliveries.iter().any(|d| d.error.is_none());
+    let record = Record {
+        sent_at: ctx.clock.now().with_timezone(&Utc),
+        kind: RecordKind::Announcement,
+        text: message,
+        values: composed.values,
+        phrases,
+        deliveries,
+    };
+    if let Some(mut state) = state {
+        state.phrases = ctx.catalog.usage();
+        state.history.push(record.clone());
+        if let Err(e) = ctx.store.store(&mut state) {
+            warn!("State: {}", e);
+        }
+    }
+
+    if !delivered {
+        return Err("No sink received the announcement".into());
+    }
+
+    Ok(Some(record))
+}
+
+/// Send `text` to every sink, rewritten first when `rewrite` is set. `None`
+/// when it was dropped, as [`announce`] does.
+pub async fn say(
+    ctx: &Context,
+    text: &str,
+    rewrite: bool,
+) -> Result<Option<Record>, Box<dyn error::Error>> {
+    let mut message = text.to_string();
+    if rewrite && ctx.rewriter.backend().is_some() {
+        match ctx.rewriter.rewrite(text).await {
+            Ok(Some(rewritten)) => message = rewritten,
+            Ok(None) => return Ok(None),
+            Err(e) => warn!("Rewrite: {}, keeping the original message", e),
+        }
+    }
+
+    // A state that cannot be read is left untouched
+    let state = match ctx.store.load() {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("State: {}", e);
+            None
+        }
+    };
+
+    let deliveries = ctx.delivery.send(&message).await;
+    let delivered = deliveries.iter().any(|d| d.error.is_none());
+    let record = Record {
+        sent_at: ctx.clock.now().with_timezone(&Utc),
+        kind: RecordKind::Say,
+        text: message,
+        values: BTreeMap::new(),
+        phrases: Vec::new(),
+        deliveries,
+    };
+    if let Some(mut state) = state {
+        state.history.push(record.clone());
+        if let Err(e) = ctx.store.store(&mut state) {
+            warn!("State: {}", e);
+        }
+    }
+
+    if !delivered {
+        return Err("No sink received the message".into());
+    }
+
+    Ok(Some(record))
+}
+
+/// Section of a preview
+#[derive(Serialize, Debug)]
+pub struct SectionPreview {
+    pub name: String,
+    pub text: String,
+}
+
+/// Announcement as it would be sent, with what it is made of
+#[derive(Serialize, Debug)]
+pub struct Preview {
+    pub time: DateTime<Local>,
+    pub template: String,
+    pub sections: Vec<SectionPreview>,
+    /// Rounded values, by metric name
+    pub values: BTreeMap<String, f64>,
+    pub phrases: Vec<Pick>,
+    pub message: String,
+    /// Rewrite of the model, when asked for
+    pub rewrite: Option<String>,
+    /// Why the rewrite asked for is missing, the original would be sent
+    /// unless the announcement is dropped
+    pub rewrite_error: Option<String>,
+}
+
+/// Compose an announcement and rewrite it when `llm` is set, a failed rewrite
+/// being reported in `rewrite_error`. Nothing is sent and the state is left
+/// as it was.
+pub async fn preview(
+    ctx: &Context,
+    time: DateTime<Local>,
+    template: &str,
+    llm: bool,
+) -> Result<Preview, Box<dyn error::Error>> {
+    if let Ok(state) = ctx.store.load() {
+        ctx.catalog.set_usage(state.phrases);
+    }
+    ctx.catalog.take_picks();
+    let composed = compose(ctx, time, template).await?;
+    let phrases = ctx.catalog.take_picks();
+
+    let (rewrite, rewrite_error) = match (llm, ctx.rewriter.backend()) {
+        (false, _) => (None, None),
+        (true, None) => (
+            None,
+            Some("the rewrite is disabled, [llm] backend is none".to_string()),
+        ),
+        (true, Some(_)) => match ctx.rewriter.rewrite(&composed.text).await {
+            Ok(Some(rewrite)) => (Some(rewrite), None),
+            Ok(None) => (
+                None,
+                Some("the announcement would be dropped as [llm] on_timeout asks".to_string()),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        },
+    };
+
+    Ok(Preview {
+        time,
+        template: template.to_string(),
+        sections: ctx
+            .sections
+            .iter()
+            .zip(composed.sections)
+            .map(|(section, text)| SectionPreview {
+                name: section.name.clone(),
+                text,
+            })
+            .collect(),
+        values: composed.values,
+        phrases,
+        message: composed.text,
+        rewrite,
+        rewrite_error,
+    })
+}
+
+/// Check every alert rule, notify its events and persist the new states.
+/// Returns the alerts sent.
+pub async fn check_alerts(ctx: &Context) -> Result<Vec<Record>, Box<dyn error::Error>> {
+    let mut state = ctx.store.load()?;
+    let rules: Vec<alerts::RuleConfig> = ctx
+        .config
+        .alerts
+        .rules
+        .iter()
+        .map(|r| r.resolve(&ctx.config))
+        .collect();
+
+    let queries: Vec<(&str, &str)> = rules
+        .iter()
+        .map(|r| (r.source.as_str(), r.query.as_str()))
+        .collect();
+    let readings = ctx
+        .metrics
+        .fetch(&queries, &[], Duration::ZERO, Duration::from_secs(1))
+        .await;
+
+    let now = ctx.clock.now().with_timezone(&Utc);
+    let mut sent = Vec::new();
+    for rule in rules.iter() {
+        // A missing value leaves the alert as it was
+        let value = match readings.get(&rule.source, &rule.query) {
+            Some(v) => v.rounded(rule.decimals),
+            None => continue,
+        };
+        let (rule_state, event) = alerts::step(
+            rule,
+            state.alerts.get(&rule.name),
+            value,
+            now,
+            ctx.config.alerts.renotify,
+        );
+        state.alerts.insert(rule.name.clone(), rule_state);
+
+        let event = match event {
+            Some(e) => e,
+            None => continue,
+        };
+        info!("Alert {}: {:?} at {}", rule.name, event, value);
+        if let Some(text) = rule.message(&event, value, &ctx.catalog) {
+            let deliveries = ctx.delivery.send(&text).await;
+            if deliveries.iter().all(|d| d.error.is_some()) {
+                warn!("Alert {}: no sink received it", rule.name);
+            }
+            let record = Record {
+                sent_at: now,
+                kind: RecordKind::Alert,
+                text,
+                values: BTreeMap::from([(rule.name.clone(), value)]),
+                phrases: Vec::new(),
+                deliveries,
+            };
+            state.history.push(record.clone());
+            sent.push(record);
+        }
+    }
+
+    ctx.store.store(&mut state)?;
+    Ok(sent)
+}
+
+/// Template of the slot at `time`, the default one between slots
+pub fn template_at(config: &Config, time: &DateTime<Local>) -> String {
+    Schedule::new(config.schedule.clone())
+        .slot_at(time)
+        .and_then(|slot| slot.template.clone())
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string())
+}
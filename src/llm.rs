@@ -0,0 +1,862 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use handlebars::Handlebars;
+use log::warn;
+use ollama_rs::generation::completion::request::GenerationRequest;
+use ollama_rs::generation::options::GenerationOptions;
+use ollama_rs::generation::parameters::{KeepAlive, TimeUnit};
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::time::Duration;
+
+use crate::catalog::{Catalog, Example};
+use crate::config::Llm;
+use crate::locale::Locale;
+
+/// Prompt compiled into the binary
+const EMBEDDED_PROMPT: &str = include_str!("../prompts/rewrite.hbs");
+
+/// Split the Ollama URL into scheme with host, and port.
+/// Without a port, the default one of the scheme is used, so an `https://`
+/// URL behind a proxy works.
+pub fn endpoint(url: &str) -> Result<(String, u16), String> {
+    let url = reqwest::Url::parse(url).map_err(|e| format!("invalid URL '{}': {}", url, e))?;
+    if url.path() != "/" || url.query().is_some() {
+        return Err(format!("'{}' must not have a path", url));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("'{}' has no host", url))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| format!("'{}' has no port", url))?;
+    Ok((format!("{}://{}", url.scheme(), host), port))
+}
+
+/// Parse a keep-alive duration: `-1` keeps the model loaded, `0` unloads it
+/// right away, otherwise a number followed by `s`, `m` or `h`
+pub fn keep_alive(value: &str) -> Result<KeepAlive, String> {
+    let value = value.trim();
+    match value {
+        "-1" => return Ok(KeepAlive::Indefinitely),
+        "0" => return Ok(KeepAlive::UnloadOnCompletion),
+        _ => {}
+    }
+
+    let invalid = || {
+        format!(
+            "invalid keep-alive '{}', expected e.g. 30s, 5m or 1h",
+            value
+        )
+    };
+    let (time, unit) = value.split_at(value.len().saturating_sub(1));
+    let unit = match unit {
+        "s" => TimeUnit::Seconds,
+        "m" => TimeUnit::Minutes,
+        "h" => TimeUnit::Hours,
+        _ => return Err(invalid()),
+    };
+    let time = time.parse().map_err(|_| invalid())?;
+    Ok(KeepAlive::Until { time, unit })
+}
+
+/// Values available to the prompt template
+#[derive(Serialize)]
+struct PromptValues<'a> {
+    message: &'a str,
+    language: &'a str,
+    tone: &'a str,
+    max_length: usize,
+    examples: &'a [Example],
+}
+
+/// Words of the introductions models write before the answer, lowercase
+const INTRODUCTIONS: [&str; 9] = [
+    "voici",
+    "reformul",
+    "here is",
+    "here's",
+    "rewrite",
+    "rewritten",
+    "hier ist",
+    "aquí",
+    "version",
+];
+
+/// Remove an introduction ending with a colon at the start of the line, as in
+/// "Voici une version reformulée : Bonjour…", keeping labels such as "Météo :"
+fn strip_introduction(line: &str) -> &str {
+    match line.split_once(':') {
+        Some((head, rest))
+            if head.chars().count() <= 80
+                && !rest.trim().is_empty()
+                && INTRODUCTIONS
+                    .iter()
+                    .any(|w| head.to_lowercase().contains(w)) =>
+        {
+            rest.trim()
+        }
+        _ => line,
+    }
+}
+
+/// Remove what models add around the answer: reasoning blocks, introductions
+/// such as "Voici une version reformulée :", markdown and enclosing quotes
+pub fn clean(response: &str) -> String {
+    let mut text = response.to_string();
+    while let Some(start) = text.find("<think>") {
+        match text[start..].find("</think>") {
+            Some(end) => text.replace_range(start..start + end + "</think>".len(), ""),
+            None => text.truncate(start),
+        }
+    }
+
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|l| {
+            l.trim()
+                .trim_start_matches('#')
+                .trim_start_matches("- ")
+                .trim_start_matches("* ")
+                .replace("**", "")
+                .replace("__", "")
+                .replace('`', "")
+        })
+        .filter(|l| !l.trim().is_empty())
+        // Notes such as "(Note : ...)"
+        .filter(|l| !(l.starts_with('(') && l.ends_with(')')))
+        .collect();
+    if lines.len() > 1 && lines[0].trim_end().ends_with(':') {
+        lines.remove(0);
+    }
+    if let Some(first) = lines.first_mut() {
+        *first = strip_introduction(first).to_string();
+    }
+
+    let mut text = lines
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    for (open, close) in [("\"", "\""), ("«", "»"), ("“", "”"), ("'", "'")] {
+        if text.len() > open.len() + close.len() && text.starts_with(open) && text.ends_with(close)
+        {
+            text = text[open.len()..text.len() - close.len()]
+                .trim()
+                .to_string();
+        }
+    }
+    text
+}
+
+/// Numbers of a text, signs dropped and decimal commas written as points
+fn numbers(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut numbers = Vec::new();
+    let mut current = String::new();
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_digit() {
+            current.push(*c);
+        } else if (*c == ',' || *c == '.')
+            && !current.is_empty()
+            && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())
+        {
+            current.push('.');
+        } else if !current.is_empty() {
+            numbers.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        numbers.push(current);
+    }
+    numbers
+}
+
+/// Check that the rewrite still says what the message said
+pub fn validate(message: &str, rewrite: &str) -> Result<(), String> {
+    if rewrite.is_empty() {
+        return Err("the rewrite is empty".to_string());
+    }
+    let kept = numbers(rewrite);
+    for number in numbers(message) {
+        if !kept.contains(&number) {
+            return Err(format!("the rewrite lost the value {}", number));
+        }
+    }
+    Ok(())
+}
+
+pub type LlmError = Box<dyn error::Error + Send + Sync>;
+
+/// Server generating the rewrite
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    /// Name used in the logs
+    fn name(&self) -> &str;
+
+    /// Stream the answer of the model to `prompt`, each piece is given to
+    /// `out` as soon as it arrives
+    async fn generate(
+        &self,
+        prompt: &str,
+        out: &mut (dyn for<'a> FnMut(&'a str) + Send),
+    ) -> Result<(), LlmError>;
+}
+
+/// API spoken by the `[llm]` server
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Ollama `/api/generate`
+    #[default]
+    Ollama,
+    /// `/v1/chat/completions` of vLLM, llama.cpp server, LocalAI...
+    Openai,
+    /// No rewrite, the composed message is sent as is
+    None,
+}
+
+/// What to send when the model has not finished within `[llm] timeout`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OnTimeout {
+    /// The composed message, as if there was no rewrite
+    #[default]
+    Original,
+    /// What the model wrote so far, cut after its last complete sentence, when
+    /// it keeps every value of the message, the composed message otherwise
+    Partial,
+    /// Nothing: the whole announcement of this slot is dropped, not only the
+    /// rewrite, for setups where a late announcement is worse than none
+    Drop,
+}
+
+/// Build the backend selected by `config`, `None` when the rewrite is disabled
+pub fn backend(config: &Llm) -> Result<Option<Box<dyn LlmBackend>>, Box<dyn error::Error>> {
+    let backend: Box<dyn LlmBackend> = match config.backend {
+        BackendKind::Ollama => {
+            let (host, port) = endpoint(&config.url).map_err(|e| format!("llm.url: {}", e))?;
+            let keep_alive = match &config.keep_alive {
+                Some(value) => {
+                    Some(keep_alive(value).map_err(|e| format!("llm.keep_alive: {}", e))?)
+                }
+                None => None,
+            };
+            Box::new(OllamaBackend {
+                http: reqwest::Client::new(),
+                url: format!("{}:{}/api/generate", host, port),
+                config: config.clone(),
+                keep_alive,
+            })
+        }
+        BackendKind::Openai => Box::new(OpenAiBackend {
+            http: reqwest::Client::new(),
+            url: format!("{}/v1/chat/completions", config.url.trim_end_matches('/')),
+            config: config.clone(),
+            api_key: match &config.api_key {
+                Some(secret) => Some(secret.read().map_err(|e| format!("llm.api_key: {}", e))?),
+                None => None,
+            },
+        }),
+        BackendKind::None => return Ok(None),
+    };
+    Ok(Some(backend))
+}
+
+/// Call `on_line` with each line of a streamed response body
+async fn read_lines(
+    response: reqwest::Response,
+    mut on_line: impl FnMut(&str) -> Result<(), LlmError>,
+) -> Result<(), LlmError> {
+    let mut stream = response.error_for_status()?.bytes_stream();
+    let mut buffer = Vec::new();
+
+    while let Some(chunk) = stream.next().await {
+        buffer.extend_from_slice(&chunk?);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            on_line(String::from_utf8_lossy(&line).trim())?;
+        }
+    }
+    if !buffer.is_empty() {
+        on_line(String::from_utf8_lossy(&buffer).trim())?;
+    }
+    Ok(())
+}
+
+struct OllamaBackend {
+    http: reqwest::Client,
+    url: String,
+    config: Llm,
+    keep_alive: Option<KeepAlive>,
+}
+
+impl OllamaBackend {
+    fn options(&self) -> GenerationOptions {
+        let c = &self.config;
+        let mut options = GenerationOptions::default();
+        if let Some(temperature) = c.temperature {
+            options = options.temperature(temperature as f32);
+        }
+        if let Some(top_p) = c.top_p {
+            options = options.top_p(top_p as f32);
+        }
+        if let Some(num_predict) = c.num_predict {
+            options = options.num_predict(num_predict);
+        }
+        if let Some(num_ctx) = c.num_ctx {
+            options = options.num_ctx(num_ctx);
+        }
+        if let Some(seed) = c.seed {
+            options = options.seed(seed);
+        }
+        options
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OllamaBackend {
+    fn name(&self) -> &str {
+        "Ollama"
+    }
+
+    async fn generate(
+        &self,
+        prompt: &str,
+        out: &mut (dyn for<'a> FnMut(&'a str) + Send),
+    ) -> Result<(), LlmError> {
+        #[derive(Deserialize)]
+        struct Chunk {
+            #[serde(default)]
+            response: String,
+            error: Option<String>,
+        }
+
+        let mut request = GenerationRequest::new(self.config.model.to_string(), prompt.to_string())
+            .options(self.options());
+        if let Some(system) = &self.config.system {
+            request = request.system(system.to_string());
+        }
+        if let Some(keep_alive) = &self.keep_alive {
+            request = request.keep_alive(keep_alive.clone());
+        }
+        // ollama-rs streams are not Send, the request is only borrowed for its fields
+        let mut body = serde_json::to_value(&request)?;
+        body["stream"] = serde_json::Value::Bool(true);
+
+        let response = self.http.post(&self.url).json(&body).send().await?;
+        read_lines(response, |line| {
+            if line.is_empty() {
+                return Ok(());
+            }
+            let chunk: Chunk = serde_json::from_str(line)?;
+            if let Some(error) = chunk.error {
+                return Err(error.into());
+            }
+            out(&chunk.response);
+            Ok(())
+        })
+        .await
+    }
+}
+
+struct OpenAiBackend {
+    http: reqwest::Client,
+    url: String,
+    config: Llm,
+    api_key: Option<String>,
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiBackend {
+    fn name(&self) -> &str {
+        "OpenAI-compatible"
+    }
+
+    async fn generate(
+        &self,
+        prompt: &str,
+        out: &mut (dyn for<'a> FnMut(&'a str) + Send),
+    ) -> Result<(), LlmError> {
+        #[derive(Serialize)]
+        struct ChatMessage<'a> {
+            role: &'a str,
+            content: &'a str,
+        }
+
+        #[derive(Serialize)]
+        struct ChatRequest<'a> {
+            model: &'a str,
+            messages: Vec<ChatMessage<'a>>,
+            stream: bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            temperature: Option<f64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            top_p: Option<f64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            max_tokens: Option<i32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            seed: Option<i32>,
+        }
+
+        #[derive(Deserialize)]
+        struct ChatChunk {
+            choices: Vec<Choice>,
+        }
+
+        #[derive(Deserialize)]
+        struct Choice {
+            delta: Delta,
+        }
+
+        #[derive(Deserialize)]
+        struct Delta {
+            content: Option<String>,
+        }
+
+        let c = &self.config;
+        let mut messages = Vec::new();
+        if let Some(system) = &c.system {
+            messages.push(ChatMessage {
+                role: "system",
+                content: system,
+            });
+        }
+        messages.push(ChatMessage {
+            role: "user",
+            content: prompt,
+        });
+
+        let mut request = self.http.post(&self.url).json(&ChatRequest {
+            model: &c.model,
+            messages,
+            stream: true,
+            temperature: c.temperature,
+            top_p: c.top_p,
+            max_tokens: c.num_predict,
+            seed: c.seed,
+        });
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        // Server-sent events, one `data: {json}` line per chunk
+        read_lines(request.send().await?, |line| {
+            let data = match line.strip_prefix("data:") {
+                Some(data) => data.trim(),
+                None => return Ok(()),
+            };
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            let chunk: ChatChunk = serde_json::from_str(data)?;
+            for choice in chunk.choices {
+                if let Some(content) = choice.delta.content {
+                    out(&content);
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Beginning of `text` up to its last complete sentence
+fn cut_at_sentence(text: &str) -> &str {
+    let mut end = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|(_, n)| *n);
+        if matches!(c, '.' | '!' | '?' | '…') && next.is_none_or(char::is_whitespace) {
+            end = i + c.len_utf8();
+        }
+    }
+    text[..end].trim()
+}
+
+/// Rewrites the composed message with a language model
+pub struct Rewriter {
+    backend: Option<Box<dyn LlmBackend>>,
+    config: Llm,
+    prompt: Handlebars<'static>,
+    language: &'static str,
+    examples: Vec<Example>,
+}
+
+impl Rewriter {
+    pub fn new(
+        config: &Llm,
+        locale: Locale,
+        catalog: &Catalog,
+    ) -> Result<Rewriter, Box<dyn error::Error>> {
+        let mut prompt = Handlebars::new();
+        prompt.register_escape_fn(handlebars::no_escape);
+        prompt.set_strict_mode(true);
+        match &config.prompt {
+            Some(path) => prompt
+                .register_template_file("prompt", path)
+                .map_err(|e| format!("llm.prompt: {}: {}", path.display(), e))?,
+            None => prompt
+                .register_template_string("prompt", EMBEDDED_PROMPT)
+                .map_err(|e| format!("Embedded prompt: {}", e))?,
+        }
+
+        let examples = if config.examples.is_empty() {
+            catalog.examples().to_vec()
+        } else {
+            config.examples.clone()
+        };
+
+        Ok(Rewriter {
+            backend: backend(config)?,
+            config: config.clone(),
+            prompt,
+            language: locale.language(),
+            examples,
+        })
+    }
+
+    /// Name of the backend, `None` when the rewrite is disabled
+    pub fn backend(&self) -> Option<&str> {
+        self.backend.as_ref().map(|b| b.name())
+    }
+
+    /// Prompt sent to the model for `message`
+    pub fn prompt(&self, message: &str) -> Result<String, Box<dyn error::Error>> {
+        let values = PromptValues {
+            message,
+            language: self.language,
+            tone: &self.config.tone,
+            max_length: self.config.max_length,
+            examples: &self.examples,
+        };
+        Ok(self
+            .prompt
+            .render("prompt", &values)
+            .map_err(|e| format!("Prompt: {}", e))?)
+    }
+
+    /// Rewrite `message`, fails when the answer does not keep its content.
+    /// Without backend the message is returned unchanged, `None` means the
+    /// announcement is dropped as asked by `on_timeout`.
+    pub async fn rewrite(&self, message: &str) -> Result<Option<String>, Box<dyn error::Error>> {
+        let backend = match &self.backend {
+            Some(b) => b,
+            None => return Ok(Some(message.to_string())),
+        };
+
+        let prompt = self.prompt(message)?;
+        let mut partial = String::new();
+        let budget = Duration::from_secs(self.config.timeout);
+        let result = tokio::time::timeout(
+            budget,
+            backend.generate(&prompt, &mut |piece| partial.push_str(piece)),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(())) => {
+                let rewrite = clean(&partial);
+                validate(message, &rewrite)?;
+                Ok(Some(rewrite))
+            }
+            Ok(Err(e)) => Err(e.to_string().into()),
+            Err(_) => {
+                let late = format!(
+                    "{} did not finish within {}s",
+                    backend.name(),
+                    self.config.timeout
+                );
+                match self.config.on_timeout {
+                    OnTimeout::Original => Err(late.into()),
+                    OnTimeout::Partial => {
+                        let rewrite = cut_at_sentence(&clean(&partial)).to_string();
+                        if rewrite.is_empty() {
+                            return Err(format!("{} without a complete sentence", late).into());
+                        }
+                        validate(message, &rewrite)
+                            .map_err(|e| format!("{}, partial rewrite: {}", late, e))?;
+                        warn!(
+                            "{}, sending the {} first characters of the rewrite",
+                            late,
+                            rewrite.chars().count()
+                        );
+                        Ok(Some(rewrite))
+                    }
+                    OnTimeout::Drop => {
+                        warn!("{}, dropping the announcement", late);
+                        Ok(None)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Secret;
+    use tokio::io::AsyncWriteExt;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const MESSAGE: &str = "Bonjour ! Il est 8 heures. Batterie 100 %";
+
+    fn config(backend: BackendKind, url: &str) -> Llm {
+        Llm {
+            backend,
+            url: url.to_string(),
+            model: "tiny".to_string(),
+            system: Some("Be brief".to_string()),
+            temperature: Some(0.5),
+            num_predict: Some(64),
+            seed: Some(7),
+            timeout: 5,
+            ..Llm::default()
+        }
+    }
+
+    fn rewriter(config: &Llm) -> Rewriter {
+        Rewriter::new(config, Locale::Fr, &Catalog::embedded(Locale::Fr)).unwrap()
+    }
+
+    /// Ollama stream answering `pieces`, one JSON object per line
+    fn ollama_stream(pieces: &[&str]) -> String {
+        let mut body: String = pieces
+            .iter()
+            .map(|p| {
+                serde_json::json!({ "model": "tiny", "response": p, "done": false }).to_string()
+                    + "\n"
+            })
+            .collect();
+        body.push_str(
+            &serde_json::json!({ "model": "tiny", "response": "", "done": true }).to_string(),
+        );
+        body
+    }
+
+    /// Server-sent events of a chat completion answering `pieces`
+    fn openai_stream(pieces: &[&str]) -> String {
+        let mut body: String = pieces
+            .iter()
+            .map(|p| {
+                let chunk = serde_json::json!({
+                    "object": "chat.completion.chunk",
+                    "choices": [{ "index": 0, "delta": { "content": p } }],
+                });
+                format!("data: {}\n\n", chunk)
+            })
+            .collect();
+        body.push_str("data: [DONE]\n\n");
+        body
+    }
+
+    /// Server sending `pieces` of an Ollama stream then never finishing
+    async fn stalled_server(pieces: &'static [&'static str]) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 4096];
+            let _ = tokio::io::AsyncReadExt::read(&mut socket, &mut request).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for piece in pieces {
+                let line =
+                    serde_json::json!({ "response": piece, "done": false }).to_string() + "\n";
+                socket.write_all(line.as_bytes()).await.unwrap();
+            }
+            socket.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn ollama_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "tiny",
+                "system": "Be brief",
+                "stream": true,
+                "options": { "num_predict": 64, "seed": 7 },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(ollama_stream(&[
+                "Voici le message :\n\n« Bonjour, ",
+                "il est 8 heures et la batterie ",
+                "est à 100 % ! »",
+            ])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rewriter = rewriter(&config(BackendKind::Ollama, &server.uri()));
+        assert_eq!(rewriter.backend(), Some("Ollama"));
+        assert_eq!(
+            rewriter.rewrite(MESSAGE).await.unwrap().as_deref(),
+            Some("Bonjour, il est 8 heures et la batterie est à 100 % !")
+        );
+    }
+
+    #[tokio::test]
+    async fn ollama_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                serde_json::json!({ "error": "model 'tiny' not found" }).to_string(),
+            ))
+            .mount(&server)
+            .await;
+
+        let rewriter = rewriter(&config(BackendKind::Ollama, &server.uri()));
+        let error = rewriter.rewrite(MESSAGE).await.unwrap_err();
+        assert_eq!(error.to_string(), "model 'tiny' not found");
+    }
+
+    #[tokio::test]
+    async fn openai_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "tiny",
+                "stream": true,
+                "temperature": 0.5,
+                "max_tokens": 64,
+                "seed": 7,
+                "messages": [
+                    { "role": "system", "content": "Be brief" },
+                    { "role": "user" },
+                ],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(openai_stream(&[
+                "**Il est 8 heures, ",
+                "batterie à 100 %.**",
+            ])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = config(BackendKind::Openai, &format!("{}/", server.uri()));
+        std::env::set_var("NOTIFIER_TEST_LLM_API_KEY", "secret");
+        config.api_key = Some(Secret::Env("NOTIFIER_TEST_LLM_API_KEY".to_string()));
+        let rewriter = rewriter(&config);
+        assert_eq!(
+            rewriter.rewrite(MESSAGE).await.unwrap().as_deref(),
+            Some("Il est 8 heures, batterie à 100 %.")
+        );
+    }
+
+    #[tokio::test]
+    async fn openai_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let rewriter = rewriter(&config(BackendKind::Openai, &server.uri()));
+        let error = rewriter.rewrite(MESSAGE).await.unwrap_err();
+        assert!(error.to_string().contains("503"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn lost_numbers_are_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(openai_stream(&["Bonjour, la batterie est pleine."])),
+            )
+            .mount(&server)
+            .await;
+
+        let rewriter = rewriter(&config(BackendKind::Openai, &server.uri()));
+        let error = rewriter.rewrite(MESSAGE).await.unwrap_err();
+        assert_eq!(error.to_string(), "the rewrite lost the value 8");
+    }
+
+    #[tokio::test]
+    async fn none_backend_keeps_the_message() {
+        let rewriter = rewriter(&config(BackendKind::None, "http://127.0.0.1:1"));
+        assert_eq!(rewriter.backend(), None);
+        assert_eq!(
+            rewriter.rewrite(MESSAGE).await.unwrap().as_deref(),
+            Some(MESSAGE)
+        );
+    }
+
+    #[test]
+    fn clean_answers() {
+        assert_eq!(
+            clean("<think>Court.</think>\nVoici une version reformulée :\n**Bonjour** !"),
+            "Bonjour !"
+        );
+        assert_eq!(
+            clean("Voici une version reformulée : Bonjour ! Il est 8 heures."),
+            "Bonjour ! Il est 8 heures."
+        );
+        assert_eq!(
+            clean("Here's the rewritten message: \"Hello! It is 8 AM.\""),
+            "Hello! It is 8 AM."
+        );
+        // A label of the message is not an introduction
+        assert_eq!(
+            clean("Météo : il fait beau. Batterie : 100 %."),
+            "Météo : il fait beau. Batterie : 100 %."
+        );
+    }
+
+    const STALLED: &[&str] = &[
+        "Bonjour ! Il est 8 heures, batterie à 100 %. ",
+        "Belle journée à to",
+    ];
+
+    #[tokio::test]
+    async fn timeout_keeps_the_original() {
+        let mut config = config(BackendKind::Ollama, &stalled_server(STALLED).await);
+        config.timeout = 1;
+        let error = rewriter(&config).rewrite(MESSAGE).await.unwrap_err();
+        assert_eq!(error.to_string(), "Ollama did not finish within 1s");
+    }
+
+    #[tokio::test]
+    async fn timeout_sends_the_complete_sentences() {
+        let mut config = config(BackendKind::Ollama, &stalled_server(STALLED).await);
+        config.timeout = 1;
+        config.on_timeout = OnTimeout::Partial;
+        assert_eq!(
+            rewriter(&config).rewrite(MESSAGE).await.unwrap().as_deref(),
+            Some("Bonjour ! Il est 8 heures, batterie à 100 %.")
+        );
+    }
+
+    #[tokio::test]
+    async fn timeout_rejects_a_partial_losing_values() {
+        const LOSSY: &[&str] = &["Bonjour ! Il est 8 heures. ", "La batterie est à 1"];
+        let mut config = config(BackendKind::Ollama, &stalled_server(LOSSY).await);
+        config.timeout = 1;
+        config.on_timeout = OnTimeout::Partial;
+        let error = rewriter(&config).rewrite(MESSAGE).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Ollama did not finish within 1s, partial rewrite: the rewrite lost the value 100"
+        );
+    }
+
+    #[tokio::test]
+    async fn timeout_drops_the_announcement() {
+        let mut config = config(BackendKind::Ollama, &stalled_server(STALLED).await);
+        config.timeout = 1;
+        config.on_timeout = OnTimeout::Drop;
+        assert_eq!(rewriter(&config).rewrite(MESSAGE).await.unwrap(), None);
+    }
+}
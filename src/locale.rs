@@ -0,0 +1,45 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Language of the announcements
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Fr,
+    En,
+    De,
+    Es,
+}
+
+impl Locale {
+    /// Name of the locale, also the name of its directory in the phrase directory
+    pub fn name(&self) -> &'static str {
+        match self {
+            Locale::Fr => "fr",
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Es => "es",
+        }
+    }
+
+    /// Name of the language, as given to the model rewriting the announcements
+    pub fn language(&self) -> &'static str {
+        match self {
+            Locale::Fr => "French",
+            Locale::En => "English",
+            Locale::De => "German",
+            Locale::Es => "Spanish",
+        }
+    }
+
+    /// Catalog compiled into the binary for this locale
+    pub fn embedded_catalog(&self) -> &'static str {
+        match self {
+            Locale::Fr => include_str!("../catalogs/fr.toml"),
+            Locale::En => include_str!("../catalogs/en.toml"),
+            Locale::De => include_str!("../catalogs/de.toml"),
+            Locale::Es => include_str!("../catalogs/es.toml"),
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use chrono::prelude::*;
+use chrono::Duration;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// One announcement slot of the daemon schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SlotConfig", into = "SlotConfig")]
+pub struct Slot {
+    /// Local time of the announcement
+    pub time: NaiveTime,
+    /// Days on which the slot fires (empty means every day)
+    pub weekdays: Vec<Weekday>,
+    /// Maximum random delay in seconds added to the slot time
+    pub jitter: u64,
+    /// Template rendered for this slot instead of the default one
+    pub template: Option<String>,
+}
+
+impl Slot {
+    fn fires_on(&self, day: Weekday) -> bool {
+        self.weekdays.is_empty() || self.weekdays.contains(&day)
+    }
+
+    /// Pick a random delay within the jitter window of the slot
+    pub fn pick_jitter(&self) -> std::time::Duration {
+        if self.jitter == 0 {
+            return std::time::Duration::ZERO;
+        }
+        let mut rng = rand::thread_rng();
+        std::time::Duration::from_secs(rng.gen_range(0..=self.jitter))
+    }
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.trim()
+        .parse::<Weekday>()
+        .map_err(|_| format!("invalid weekday '{}'", s))
+}
+
+fn parse_weekdays(spec: &str) -> Result<Vec<Weekday>, String> {
+    let mut days = Vec::new();
+
+    for part in spec.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let mut day = parse_weekday(from)?;
+                let last = parse_weekday(to)?;
+                days.push(day);
+                while day != last {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(parse_weekday(part)?),
+        }
+    }
+
+    Ok(days)
+}
+
+impl FromStr for Slot {
+    type Err = String;
+
+    /// Parse a slot written as `HH:MM[@days][~jitter]`, for example
+    /// `08:00`, `12:30@mon-fri` or `19:00@sat,sun~300`.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (rest, jitter) = match spec.split_once('~') {
+            Some((rest, jitter)) => (
+                rest,
+                jitter
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid jitter '{}' in slot '{}'", jitter, spec))?,
+            ),
+            None => (spec, 0),
+        };
+
+        let (time, weekdays) = match rest.split_once('@') {
+            Some((time, days)) => (time, parse_weekdays(days)?),
+            None => (rest, Vec::new()),
+        };
+
+        let time = NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|_| format!("invalid time '{}' in slot '{}'", time, spec))?;
+
+        Ok(Slot {
+            time,
+            weekdays,
+            jitter,
+            template: None,
+        })
+    }
+}
+
+/// Slot as written in the `[[schedule]]` tables of the configuration file
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SlotConfig {
+    time: String,
+    #[serde(default)]
+    weekdays: Vec<String>,
+    #[serde(default)]
+    jitter: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    template: Option<String>,
+}
+
+impl TryFrom<SlotConfig> for Slot {
+    type Error = String;
+
+    fn try_from(c: SlotConfig) -> Result<Self, Self::Error> {
+        let time = NaiveTime::parse_from_str(&c.time, "%H:%M")
+            .map_err(|_| format!("invalid time '{}' in schedule", c.time))?;
+        let weekdays = if c.weekdays.is_empty() {
+            Vec::new()
+        } else {
+            parse_weekdays(&c.weekdays.join(","))?
+        };
+
+        Ok(Slot {
+            time,
+            weekdays,
+            jitter: c.jitter,
+            template: c.template,
+        })
+    }
+}
+
+impl From<Slot> for SlotConfig {
+    fn from(s: Slot) -> Self {
+        SlotConfig {
+            time: s.time.format("%H:%M").to_string(),
+            weekdays: s
+                .weekdays
+                .iter()
+                .map(|d| d.to_string().to_lowercase())
+                .collect(),
+            jitter: s.jitter,
+            template: s.template,
+        }
+    }
+}
+
+/// The hours for which the composer has a dedicated phrase
+pub const DEFAULT_SLOTS: &str = "08:00,10:00,12:00,15:00,16:00,19:00,20:00";
+
+/// Set of slots the daemon fires on
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub slots: Vec<Slot>,
+}
+
+impl Schedule {
+    pub fn new(slots: Vec<Slot>) -> Schedule {
+        Schedule { slots }
+    }
+
+    /// Slot firing at the minute of `time`, if any
+    pub fn slot_at<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> Option<&Slot> {
+        self.slots.iter().find(|slot| {
+            slot.fires_on(time.weekday())
+                && slot.time.hour() == time.hour()
+                && slot.time.minute() == time.minute()
+        })
+    }
+
+    /// Return the first slot strictly after `now` with its local date
+    pub fn next_after<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<(DateTime<Tz>, &Slot)> {
+        let tz = now.timezone();
+        let today = now.date_naive();
+        let mut next: Option<(DateTime<Tz>, &Slot)> = None;
+
+        for slot in self.slots.iter() {
+            for offset in 0..=7 {
+                let date = today + Duration::days(offset);
+
+                if !slot.fires_on(date.weekday()) {
+                    continue;
+                }
+
+                let candidate = match tz.from_local_datetime(&date.and_time(slot.time)).earliest() {
+                    Some(c) => c,
+                    // Slot falls in a DST gap
+                    None => continue,
+                };
+
+                if candidate <= *now {
+                    continue;
+                }
+
+                if next.as_ref().is_none_or(|(n, _)| candidate < *n) {
+                    next = Some((candidate, slot));
+                }
+                break;
+            }
+        }
+
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::LocalResult;
+
+    /// Central European time around the spring change of 2026-03-29,
+    /// when 02:00 local jumps to 03:00
+    #[derive(Debug, Clone, Copy)]
+    struct Paris;
+
+    fn gap() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 29)
+            .unwrap()
+            .and_hms_opt(2, 0, 0)
+            .unwrap()
+    }
+
+    fn winter() -> FixedOffset {
+        FixedOffset::east_opt(3600).unwrap()
+    }
+
+    fn summer() -> FixedOffset {
+        FixedOffset::east_opt(7200).unwrap()
+    }
+
+    impl TimeZone for Paris {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Paris {
+            Paris
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            if *local < gap() {
+                LocalResult::Single(winter())
+            } else if *local < gap() + Duration::hours(1) {
+                LocalResult::None
+            } else {
+                LocalResult::Single(summer())
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < gap() - Duration::hours(1) {
+                winter()
+            } else {
+                summer()
+            }
+        }
+    }
+
+    fn schedule(specs: &[&str]) -> Schedule {
+        Schedule::new(specs.iter().map(|s| s.parse().unwrap()).collect())
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<FixedOffset> {
+        // 2026-10-12 is a Monday
+        winter()
+            .with_ymd_and_hms(2026, 10, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_slots() {
+        let slot: Slot = "19:00@sat,sun~300".parse().unwrap();
+        assert_eq!(slot.time, NaiveTime::from_hms_opt(19, 0, 0).unwrap());
+        assert_eq!(slot.weekdays, vec![Weekday::Sat, Weekday::Sun]);
+        assert_eq!(slot.jitter, 300);
+
+        let slot: Slot = "07:30@fri-mon".parse().unwrap();
+        assert_eq!(
+            slot.weekdays,
+            vec![Weekday::Fri, Weekday::Sat, Weekday::Sun, Weekday::Mon]
+        );
+
+        for spec in [
+            "",
+            "8h",
+            "25:00",
+            "08:60",
+            "08:00@",
+            "08:00@funday",
+            "08:00@mon-",
+            "08:00~",
+            "08:00~-5",
+            "08:00@mon~soon",
+        ] {
+            assert!(spec.parse::<Slot>().is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn wrapping_weekdays() {
+        let schedule = schedule(&["07:30@fri-mon"]);
+
+        // Tuesday to Thursday are skipped
+        let (next, _) = schedule.next_after(&at(13, 8, 0)).unwrap();
+        assert_eq!(next, at(16, 7, 30));
+        assert_eq!(next.weekday(), Weekday::Fri);
+
+        // The range goes on over the weekend to Monday
+        let (next, _) = schedule.next_after(&at(18, 8, 0)).unwrap();
+        assert_eq!(next, at(19, 7, 30));
+        assert_eq!(next.weekday(), Weekday::Mon);
+    }
+
+    #[test]
+    fn strictly_after() {
+        let schedule = schedule(&["08:00", "12:00"]);
+
+        let (next, slot) = schedule.next_after(&at(14, 8, 0)).unwrap();
+        assert_eq!(next, at(14, 12, 0));
+        assert_eq!(slot.time.hour(), 12);
+
+        let (next, _) = schedule.next_after(&at(14, 12, 0)).unwrap();
+        assert_eq!(next, at(15, 8, 0));
+
+        let (next, _) = schedule.next_after(&at(14, 7, 59)).unwrap();
+        assert_eq!(next, at(14, 8, 0));
+    }
+
+    #[test]
+    fn excluded_day() {
+        let schedule = schedule(&["08:00@mon-fri", "10:00@sat"]);
+
+        // Friday evening: Saturday 10:00 comes before Monday 08:00
+        let (next, slot) = schedule.next_after(&at(16, 20, 0)).unwrap();
+        assert_eq!(next, at(17, 10, 0));
+        assert_eq!(slot.weekdays, vec![Weekday::Sat]);
+
+        // Saturday after 10:00: Sunday has no slot
+        let (next, _) = schedule.next_after(&at(17, 11, 0)).unwrap();
+        assert_eq!(next, at(19, 8, 0));
+
+        assert!(Schedule::new(Vec::new())
+            .next_after(&at(17, 11, 0))
+            .is_none());
+    }
+
+    #[test]
+    fn slot_in_dst_gap() {
+        let before = Paris.with_ymd_and_hms(2026, 3, 28, 23, 0, 0).unwrap();
+
+        // 02:30 does not exist on the 29th, the next 02:30 is on the 30th
+        let night = schedule(&["02:30"]);
+        let (next, _) = night.next_after(&before).unwrap();
+        assert_eq!(next.naive_local().date().day(), 30);
+        assert_eq!(next.naive_local().time().hour(), 2);
+        assert_eq!(next.offset().fix(), summer());
+
+        // The other slots of the day are kept
+        let both = schedule(&["02:30", "09:00@sun"]);
+        let (next, slot) = both.next_after(&before).unwrap();
+        assert_eq!(slot.time.hour(), 9);
+        assert_eq!(next.naive_local().date().day(), 29);
+    }
+
+    #[test]
+    fn slot_at_minute() {
+        let schedule = schedule(&["08:00@mon-fri", "08:00@sat~60", "12:30"]);
+
+        assert_eq!(schedule.slot_at(&at(14, 8, 0)).unwrap().jitter, 0);
+        assert_eq!(schedule.slot_at(&at(17, 8, 0)).unwrap().jitter, 60);
+        assert!(schedule.slot_at(&at(18, 8, 0)).is_none());
+        assert!(schedule.slot_at(&at(18, 8, 1)).is_none());
+
+        // Seconds within the slot minute still match
+        let late = at(18, 12, 30) + Duration::seconds(42);
+        assert_eq!(schedule.slot_at(&late).unwrap().time.minute(), 30);
+    }
+}
@@ -0,0 +1,465 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::catalog::{self, Catalog, Category};
+use crate::config::Config;
+use crate::metrics::{Readings, Series};
+
+/// Section whose metrics come from `[sources.prometheus.queries]` and
+/// `[thresholds]`, rendered by the embedded template of the same name
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Weather,
+    Electricity,
+}
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Weather => "weather",
+            Preset::Electricity => "electricity",
+        }
+    }
+}
+
+/// Metric of a section, available to its template under `name`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricConfig {
+    pub name: String,
+    pub query: String,
+    /// Unit of the value, available as `<name>_unit`
+    pub unit: String,
+    /// Digits kept after the decimal point
+    pub decimals: u32,
+    /// Range query whose evolution is available as `<name>_trend`
+    pub trend: Option<String>,
+}
+
+/// Comparison of a metric, once rounded, with a limit
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdConfig {
+    pub metric: String,
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    pub at_least: Option<f64>,
+    pub at_most: Option<f64>,
+    /// Variable set to true when the threshold is crossed
+    pub flag: Option<String>,
+    /// Sentence added after the section, `{value}` and `{unit}` are replaced
+    pub phrase: Option<String>,
+    /// Catalog category of a random phrase added after the section
+    pub category: Option<String>,
+}
+
+impl ThresholdConfig {
+    fn limits(&self) -> [Option<f64>; 4] {
+        [self.above, self.below, self.at_least, self.at_most]
+    }
+
+    fn crossed(&self, value: f64) -> bool {
+        self.above.is_some_and(|l| value > l)
+            || self.below.is_some_and(|l| value < l)
+            || self.at_least.is_some_and(|l| value >= l)
+            || self.at_most.is_some_and(|l| value <= l)
+    }
+}
+
+/// Part of the announcement about a group of metrics
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SectionConfig {
+    /// Built-in section, the other keys are then optional
+    pub preset: Option<Preset>,
+    /// Name of the section, `has_<name>` tells whether any metric is available
+    pub name: String,
+    /// Source of the queries, `prometheus` or the name of a `[sources.<name>]`
+    pub source: String,
+    /// Inline Handlebars template, the template `<name>` when empty
+    pub template: String,
+    pub metrics: Vec<MetricConfig>,
+    pub thresholds: Vec<ThresholdConfig>,
+}
+
+impl Default for SectionConfig {
+    fn default() -> Self {
+        SectionConfig {
+            preset: None,
+            name: String::new(),
+            source: "prometheus".to_string(),
+            template: String::new(),
+            metrics: Vec::new(),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+/// Sections announced when the configuration lists none
+pub fn default_sections() -> Vec<SectionConfig> {
+    [Preset::Weather, Preset::Electricity]
+        .into_iter()
+        .map(|preset| SectionConfig {
+            preset: Some(preset),
+            ..SectionConfig::default()
+        })
+        .collect()
+}
+
+impl SectionConfig {
+    /// Section with the metrics and thresholds of its preset filled in
+    pub fn resolve(&self, config: &Config) -> SectionConfig {
+        let preset = match self.preset {
+            Some(preset) => preset,
+            None => return self.clone(),
+        };
+        let queries = &config.sources.prometheus.queries;
+        let decimals = &config.sources.prometheus.decimals;
+        let trends = &config.sources.prometheus.trends;
+        let thresholds = &config.thresholds;
+
+        let metric = |name: &str, query: &str, decimals: u32| MetricConfig {
+            name: name.to_string(),
+            query: query.to_string(),
+            decimals,
+            ..MetricConfig::default()
+        };
+        let flag = |name: &str, metric: &str| ThresholdConfig {
+            metric: metric.to_string(),
+            flag: Some(name.to_string()),
+            ..ThresholdConfig::default()
+        };
+
+        let (metrics, thresholds) = match preset {
+            Preset::Weather => (
+                vec![
+                    MetricConfig {
+                        trend: Some(trends.temperature.clone()),
+                        ..metric("temperature", &queries.temperature, decimals.temperature)
+                    },
+                    metric(
+                        "temperature_min",
+                        &queries.temperature_min,
+                        decimals.temperature_min,
+                    ),
+                    metric(
+                        "temperature_max",
+                        &queries.temperature_max,
+                        decimals.temperature_max,
+                    ),
+                    metric("wind_speed", &queries.wind_speed, decimals.wind_speed),
+                    metric("humidity", &queries.humidity, decimals.humidity),
+                    metric("cloud_cover", &queries.cloud_cover, decimals.cloud_cover),
+                    metric("weather_code", &queries.weather_code, 0),
+                ],
+                vec![
+                    ThresholdConfig {
+                        above: Some(0.0),
+                        ..flag("cloudy", "cloud_cover")
+                    },
+                    ThresholdConfig {
+                        above: Some(thresholds.strong_wind as f64),
+                        ..flag("strong_wind", "wind_speed")
+                    },
+                    ThresholdConfig {
+                        below: Some(thresholds.cold_temperature as f64),
+                        ..flag("cold", "temperature_min")
+                    },
+                    ThresholdConfig {
+                        above: Some(thresholds.high_temperature as f64),
+                        ..flag("hot", "temperature_max")
+                    },
+                ],
+            ),
+            Preset::Electricity => (
+                vec![
+                    MetricConfig {
+                        trend: Some(trends.battery_soc.clone()),
+                        ..metric("soc", &queries.battery_soc, decimals.battery_soc)
+                    },
+                    metric("avgsolar_1h", &queries.avg_solar_1h, decimals.avg_solar_1h),
+                    metric("avgpower_1h", &queries.avg_power_1h, decimals.avg_power_1h),
+                ],
+                vec![
+                    ThresholdConfig {
+                        at_least: Some(thresholds.battery_full as f64),
+                        ..flag("battery_full", "soc")
+                    },
+                    ThresholdConfig {
+                        below: Some(0.0),
+                        ..flag("injecting", "avgpower_1h")
+                    },
+                ],
+            ),
+        };
+
+        SectionConfig {
+            preset: Some(preset),
+            name: preset.name().to_string(),
+            source: self.source.clone(),
+            template: self.template.clone(),
+            metrics,
+            thresholds,
+        }
+    }
+
+    /// Name of the Handlebars template rendering the section
+    pub fn template_name(&self) -> String {
+        if self.template.is_empty() {
+            self.name.clone()
+        } else {
+            format!("sections/{}", self.name)
+        }
+    }
+
+    /// Instant queries of the metrics, with their source
+    pub fn queries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.metrics
+            .iter()
+            .map(|m| (self.source.as_str(), m.query.as_str()))
+    }
+
+    /// Range queries of the trends, with their source
+    pub fn trends(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.metrics
+            .iter()
+            .filter_map(|m| Some((self.source.as_str(), m.trend.as_deref()?)))
+    }
+
+    /// Check a section as written in the configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.preset.is_some() {
+            if !self.metrics.is_empty() || !self.thresholds.is_empty() {
+                return Err(
+                    "a preset takes its metrics from [sources.prometheus] and [thresholds]"
+                        .to_string(),
+                );
+            }
+            return Ok(());
+        }
+
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!(
+                "invalid name '{}', use letters, digits and _",
+                self.name
+            ));
+        }
+        if self.metrics.is_empty() {
+            return Err(format!("{}: at least one metric is required", self.name));
+        }
+        for metric in self.metrics.iter() {
+            if metric.name.is_empty() || metric.query.is_empty() {
+                return Err(format!(
+                    "{}: each metric needs a name and a query",
+                    self.name
+                ));
+            }
+        }
+        for threshold in self.thresholds.iter() {
+            if !self.metrics.iter().any(|m| m.name == threshold.metric) {
+                return Err(format!(
+                    "{}: threshold on unknown metric '{}'",
+                    self.name, threshold.metric
+                ));
+            }
+            if threshold.limits().iter().filter(|l| l.is_some()).count() != 1 {
+                return Err(format!(
+                    "{}: threshold on '{}' needs one of above, below, at_least or at_most",
+                    self.name, threshold.metric
+                ));
+            }
+            if let Some(name) = &threshold.category {
+                if Category::from_name(name).is_none() {
+                    return Err(format!("{}: unknown category '{}'", self.name, name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Template variables of the section and the threshold phrases to add
+    /// after it
+    pub fn values(
+        &self,
+        readings: &Readings,
+        catalog: &Catalog,
+    ) -> (Map<String, Value>, Vec<String>) {
+        let mut values = Map::new();
+        let mut rounded = std::collections::HashMap::new();
+
+        for metric in self.metrics.iter() {
+            let value = readings
+                .get(&self.source, &metric.query)
+                .map(|v| v.rounded(metric.decimals));
+            if let Some(v) = value {
+                rounded.insert(metric.name.as_str(), v);
+            }
+            values.insert(
+                metric.name.clone(),
+                value.map_or(Value::Null, |v| catalog.number(v, metric.decimals).into()),
+            );
+            values.insert(format!("{}_unit", metric.name), metric.unit.clone().into());
+            if let Some(trend) = &metric.trend {
+                let trend = readings
+                    .series(&self.source, trend)
+                    .map(|s| Trend::new(s, metric.decimals, catalog));
+                values.insert(
+                    format!("{}_trend", metric.name),
+                    serde_json::to_value(trend).unwrap_or_default(),
+                );
+            }
+        }
+        values.insert(format!("has_{}", self.name), (!rounded.is_empty()).into());
+
+        let mut phrases = Vec::new();
+        for threshold in self.thresholds.iter() {
+            let value = rounded.get(threshold.metric.as_str()).copied();
+            let crossed = value.is_some_and(|v| threshold.crossed(v));
+            if let Some(flag) = &threshold.flag {
+                values.insert(flag.clone(), crossed.into());
+            }
+            let value = match value {
+                Some(v) if crossed => v,
+                _ => continue,
+            };
+            if let Some(phrase) = &threshold.phrase {
+                let metric = self.metrics.iter().find(|m| m.name == threshold.metric);
+                let (decimals, unit) = metric.map_or((0, ""), |m| (m.decimals, m.unit.as_str()));
+                phrases.push(catalog::fill(
+                    phrase,
+                    &[("value", &catalog.number(value, decimals)), ("unit", unit)],
+                ));
+            }
+            if let Some(category) = threshold.category.as_deref().and_then(Category::from_name) {
+                phrases.push(catalog.pick(category).to_string());
+            }
+        }
+
+        if self.preset == Some(Preset::Weather) {
+            let code = rounded.get("weather_code").map(|c| *c as u32);
+            values.insert(
+                "weather_description".to_string(),
+                code.map_or(Value::Null, |c| catalog.weather(c).into()),
+            );
+        }
+
+        (values, phrases)
+    }
+}
+
+/// Evolution of a metric over the trend window, as spoken
+#[derive(Serialize)]
+pub struct Trend {
+    /// Change, neither rising nor falling when it rounds to zero
+    rising: bool,
+    falling: bool,
+    /// Size of the change, without its sign
+    delta: String,
+    /// Change per hour, signed
+    slope: String,
+    min: String,
+    max: String,
+    /// Local times of the lowest and highest samples, as HH:MM
+    min_time: String,
+    max_time: String,
+}
+
+impl Trend {
+    fn new(series: &Series, decimals: u32, catalog: &Catalog) -> Trend {
+        let round = |value: f64| {
+            let factor = 10f64.powi(decimals as i32);
+            (value * factor).round() / factor
+        };
+        let delta = round(series.delta());
+        let time = |t: DateTime<Utc>| t.with_timezone(&Local).format("%H:%M").to_string();
+        let (min_time, min) = series.min();
+        let (max_time, max) = series.max();
+
+        Trend {
+            rising: delta > 0.0,
+            falling: delta < 0.0,
+            delta: catalog.number(delta.abs(), decimals),
+            slope: catalog.number(round(series.slope()), decimals),
+            min: catalog.number(round(min), decimals),
+            max: catalog.number(round(max), decimals),
+            min_time: time(min_time),
+            max_time: time(max_time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn water() -> SectionConfig {
+        SectionConfig {
+            name: "water".to_string(),
+            template: "{{level}}".to_string(),
+            metrics: vec![MetricConfig {
+                name: "level".to_string(),
+                query: "water_tank_liters".to_string(),
+                ..MetricConfig::default()
+            }],
+            thresholds: vec![ThresholdConfig {
+                metric: "level".to_string(),
+                below: Some(200.0),
+                ..ThresholdConfig::default()
+            }],
+            ..SectionConfig::default()
+        }
+    }
+
+    #[test]
+    fn thresholds() {
+        let below = &water().thresholds[0];
+        assert!(below.crossed(199.0));
+        assert!(!below.crossed(200.0));
+
+        let at_least = ThresholdConfig {
+            at_least: Some(100.0),
+            ..ThresholdConfig::default()
+        };
+        assert!(at_least.crossed(100.0));
+        assert!(!at_least.crossed(99.0));
+    }
+
+    #[test]
+    fn validation() {
+        assert!(water().validate().is_ok());
+
+        let mut section = water();
+        section.thresholds[0].metric = "volume".to_string();
+        assert!(section.validate().unwrap_err().contains("unknown metric"));
+
+        let mut section = water();
+        section.thresholds[0].above = Some(1000.0);
+        assert!(section.validate().unwrap_err().contains("needs one of"));
+
+        let mut section = water();
+        section.name = "water tank".to_string();
+        assert!(section.validate().is_err());
+
+        let mut preset = default_sections().remove(0);
+        assert!(preset.validate().is_ok());
+        preset.metrics = water().metrics;
+        assert!(preset.validate().is_err());
+    }
+
+    #[test]
+    fn presets_follow_the_configuration() {
+        let mut config = Config::default();
+        config.thresholds.battery_full = 95.0;
+        let electricity = default_sections()[1].resolve(&config);
+
+        assert_eq!(electricity.name, "electricity");
+        assert_eq!(electricity.template_name(), "electricity");
+        assert!(electricity
+            .queries()
+            .any(|q| q == ("prometheus", "imeon_battery_soc")));
+        assert_eq!(electricity.thresholds[0].at_least, Some(95.0));
+        assert_eq!(water().template_name(), "sections/water");
+    }
+}
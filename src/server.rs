@@ -0,0 +1,270 @@
+use chrono::prelude::*;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::error;
+use std::future::Future;
+use std::net::TcpListener;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::{announce, preview, say, template_at, Context};
+
+/// Largest request body accepted
+const MAX_BODY: usize = 64 * 1024;
+
+/// Body of `POST /announce`, every key is optional
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct AnnounceRequest {
+    /// Slot as HH:MM, composed as at that time today with its template
+    slot: Option<String>,
+    /// Template rendered instead of the one of the slot
+    template: Option<String>,
+}
+
+/// Query of `GET /preview`
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PreviewQuery {
+    slot: Option<String>,
+    template: Option<String>,
+    /// Rewrite with the language model too
+    llm: bool,
+}
+
+/// Body of `POST /say`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SayRequest {
+    text: String,
+    /// Rewrite with the language model before sending
+    #[serde(default = "default_rewrite")]
+    rewrite: bool,
+}
+
+fn default_rewrite() -> bool {
+    true
+}
+
+/// Error returned to the client with its status
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(message: impl ToString) -> ApiError {
+        ApiError(StatusCode::BAD_REQUEST, message.to_string())
+    }
+}
+
+fn json(status: StatusCode, value: &impl Serialize) -> Response<Body> {
+    let body = serde_json::to_string(value).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap_or_default()
+}
+
+/// Answer when the rewrite timed out and `[llm] on_timeout` drops the message
+fn dropped() -> Response<Body> {
+    json(StatusCode::OK, &serde_json::json!({ "dropped": true }))
+}
+
+fn error(ApiError(status, message): ApiError) -> Response<Body> {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+struct Api {
+    ctx: Context,
+    token: Option<String>,
+    /// One message at a time, as they share the phrase selection and the state file
+    busy: Mutex<()>,
+}
+
+impl Api {
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let route = (request.method().clone(), request.uri().path().to_string());
+        if route.1 != "/health" && !self.authorized(&request) {
+            return error(ApiError(
+                StatusCode::UNAUTHORIZED,
+                "missing or wrong bearer token".to_string(),
+            ));
+        }
+
+        let result = match (&route.0, route.1.as_str()) {
+            (&Method::GET, "/health") => {
+                Ok(json(StatusCode::OK, &serde_json::json!({ "status": "ok" })))
+            }
+            (&Method::POST, "/announce") => self.announce(request).await,
+            (&Method::GET, "/preview") => self.preview(request).await,
+            (&Method::POST, "/say") => self.say(request).await,
+            (_, "/health" | "/announce" | "/preview" | "/say") => Err(ApiError(
+                StatusCode::METHOD_NOT_ALLOWED,
+                format!("{} is not allowed on {}", route.0, route.1),
+            )),
+            _ => Err(ApiError(
+                StatusCode::NOT_FOUND,
+                format!("no route {}", route.1),
+            )),
+        };
+
+        let response = result.unwrap_or_else(error);
+        info!("{} {}: {}", route.0, route.1, response.status());
+        response
+    }
+
+    fn authorized(&self, request: &Request<Body>) -> bool {
+        let token = match &self.token {
+            Some(t) => t,
+            None => return true,
+        };
+        request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .is_some_and(|t| t == token)
+    }
+
+    /// Time and template to compose for
+    fn target(
+        &self,
+        slot: Option<&str>,
+        template: Option<String>,
+    ) -> Result<(DateTime<Local>, String), ApiError> {
+        let now = self.ctx.clock.now();
+        let time = match slot {
+            Some(slot) => {
+                let time = NaiveTime::parse_from_str(slot, "%H:%M").map_err(|_| {
+                    ApiError::bad_request(format!("slot: invalid time '{}', expected HH:MM", slot))
+                })?;
+                Local
+                    .from_local_datetime(&now.date_naive().and_time(time))
+                    .earliest()
+                    .ok_or_else(|| {
+                        ApiError::bad_request(format!("slot: {} does not exist today", slot))
+                    })?
+            }
+            None => now,
+        };
+        let template = match template {
+            Some(name) if !self.ctx.templates.has(&name) => {
+                return Err(ApiError::bad_request(format!(
+                    "unknown template '{}'",
+                    name
+                )))
+            }
+            Some(name) => name,
+            None => template_at(&self.ctx.config, &time),
+        };
+        Ok((time, template))
+    }
+
+    async fn announce(&self, request: Request<Body>) -> Result<Response<Body>, ApiError> {
+        let body = read_body(request).await?;
+        let request: AnnounceRequest = if body.iter().all(u8::is_ascii_whitespace) {
+            AnnounceRequest::default()
+        } else {
+            serde_json::from_slice(&body).map_err(ApiError::bad_request)?
+        };
+        let (time, template) = self.target(request.slot.as_deref(), request.template)?;
+
+        let _busy = self.busy.lock().await;
+        match announce(&self.ctx, time, &template).await {
+            Ok(Some(record)) => Ok(json(StatusCode::OK, &record)),
+            Ok(None) => Ok(dropped()),
+            Err(e) => Err(ApiError(StatusCode::BAD_GATEWAY, e.to_string())),
+        }
+    }
+
+    async fn preview(&self, request: Request<Body>) -> Result<Response<Body>, ApiError> {
+        let query: PreviewQuery = serde_urlencoded::from_str(request.uri().query().unwrap_or(""))
+            .map_err(ApiError::bad_request)?;
+        let (time, template) = self.target(query.slot.as_deref(), query.template)?;
+
+        let _busy = self.busy.lock().await;
+        let preview = preview(&self.ctx, time, &template, query.llm)
+            .await
+            .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        Ok(json(StatusCode::OK, &preview))
+    }
+
+    async fn say(&self, request: Request<Body>) -> Result<Response<Body>, ApiError> {
+        let body = read_body(request).await?;
+        let request: SayRequest = serde_json::from_slice(&body).map_err(ApiError::bad_request)?;
+        if request.text.trim().is_empty() {
+            return Err(ApiError::bad_request("text: empty"));
+        }
+
+        let _busy = self.busy.lock().await;
+        match say(&self.ctx, &request.text, request.rewrite).await {
+            Ok(Some(record)) => Ok(json(StatusCode::OK, &record)),
+            Ok(None) => Ok(dropped()),
+            Err(e) => Err(ApiError(StatusCode::BAD_GATEWAY, e.to_string())),
+        }
+    }
+}
+
+async fn read_body(request: Request<Body>) -> Result<hyper::body::Bytes, ApiError> {
+    let too_large = || {
+        ApiError(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("body over {} bytes", MAX_BODY),
+        )
+    };
+    let declared = request
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|l| l.to_str().ok()?.parse::<usize>().ok());
+    if declared.is_some_and(|l| l > MAX_BODY) {
+        return Err(too_large());
+    }
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .map_err(ApiError::bad_request)?;
+    if body.len() > MAX_BODY {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+/// Answer the HTTP API on `listener` until `shutdown` completes:
+/// - `POST /announce`: compose and send now, `{"slot": "HH:MM", "template": "..."}` optional
+/// - `GET /preview?slot=HH:MM&template=...&llm=true`: compose without sending
+/// - `POST /say`: send `{"text": "...", "rewrite": true}` through the rewrite and the sinks
+/// - `GET /health`
+pub async fn serve(
+    ctx: Context,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Box<dyn error::Error>> {
+    let token = match &ctx.config.server.token {
+        Some(secret) => Some(secret.read().map_err(|e| format!("server.token: {}", e))?),
+        None => None,
+    };
+    let api = Arc::new(Api {
+        ctx,
+        token,
+        busy: Mutex::new(()),
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let api = api.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let api = api.clone();
+                async move { Ok::<_, Infallible>(api.handle(request).await) }
+            }))
+        }
+    });
+
+    info!("Listening on http://{}", listener.local_addr()?);
+    hyper::Server::from_tcp(listener)?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
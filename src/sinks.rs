@@ -0,0 +1,719 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Local, Utc};
+use handlebars::Handlebars;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::Secret;
+
+pub type SinkError = Box<dyn error::Error + Send + Sync>;
+
+/// Message handed to the sinks, the same on each retry and outbox flush
+#[derive(Debug, Clone, Copy)]
+pub struct Message<'a> {
+    pub text: &'a str,
+    /// When the message was first sent
+    pub created_at: DateTime<Utc>,
+}
+
+impl Message<'_> {
+    /// Identifier of the message, for the sinks deduplicating a retried request
+    pub fn id(&self) -> String {
+        // FNV-1a, stable across builds unlike the hasher of the standard library
+        let hash = self.text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        format!(
+            "notifier-{}-{:016x}",
+            self.created_at.timestamp_millis(),
+            hash
+        )
+    }
+}
+
+/// Destination of the composed announcements
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name used when reporting the delivery result
+    fn name(&self) -> &str;
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError>;
+}
+
+/// How the SMTP connection is secured
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// STARTTLS upgrade, usually on port 587
+    #[default]
+    Starttls,
+    /// Implicit TLS, usually on port 465
+    Tls,
+    /// Plain text, only for a relay on a trusted network
+    None,
+}
+
+fn default_webhook_body() -> String {
+    "{\"text\": \"{{text}}\"}".to_string()
+}
+
+fn default_subject() -> String {
+    "Notifier".to_string()
+}
+
+/// One `[[sinks]]` entry of the configuration
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SinkConfig {
+    /// notifyd `/notify` endpoint
+    Notifyd { name: Option<String>, url: String },
+    /// ntfy topic
+    Ntfy {
+        name: Option<String>,
+        url: String,
+        topic: String,
+        token: Option<Secret>,
+        title: Option<String>,
+        priority: Option<u8>,
+    },
+    /// Gotify application
+    Gotify {
+        name: Option<String>,
+        url: String,
+        token: Secret,
+        title: Option<String>,
+        priority: Option<u8>,
+    },
+    /// Generic JSON webhook, `{{text}}` in the body is replaced by the JSON-escaped message
+    Webhook {
+        name: Option<String>,
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default = "default_webhook_body")]
+        body: String,
+    },
+    /// Email through an SMTP server
+    Email {
+        name: Option<String>,
+        host: String,
+        port: Option<u16>,
+        #[serde(default)]
+        security: SmtpSecurity,
+        username: Option<String>,
+        password: Option<Secret>,
+        from: String,
+        to: Vec<String>,
+        #[serde(default = "default_subject")]
+        subject: String,
+    },
+    /// Message in a Matrix room
+    Matrix {
+        name: Option<String>,
+        homeserver: String,
+        room_id: String,
+        access_token: Secret,
+    },
+    /// Line appended to a local file
+    File { name: Option<String>, path: PathBuf },
+    /// Line printed on the standard output
+    Stdout { name: Option<String> },
+}
+
+impl SinkConfig {
+    /// Name given in the configuration, or the sink type
+    pub fn name(&self) -> String {
+        let (name, kind) = match self {
+            SinkConfig::Notifyd { name, .. } => (name, "notifyd"),
+            SinkConfig::Ntfy { name, .. } => (name, "ntfy"),
+            SinkConfig::Gotify { name, .. } => (name, "gotify"),
+            SinkConfig::Webhook { name, .. } => (name, "webhook"),
+            SinkConfig::Email { name, .. } => (name, "email"),
+            SinkConfig::Matrix { name, .. } => (name, "matrix"),
+            SinkConfig::File { name, .. } => (name, "file"),
+            SinkConfig::Stdout { name } => (name, "stdout"),
+        };
+        name.clone().unwrap_or_else(|| kind.to_string())
+    }
+
+    /// Build the sink, sharing `http` between the HTTP based ones. `timeout`
+    /// applies to the sinks which do not go through `http`.
+    pub fn build(
+        &self,
+        http: &reqwest::Client,
+        timeout: Duration,
+    ) -> Result<Box<dyn Notifier>, Box<dyn error::Error>> {
+        let name = self.name();
+        let read = |field: &str, secret: &Secret| {
+            secret
+                .read()
+                .map_err(|e| format!("sink {}: {}: {}", self.name(), field, e))
+        };
+
+        let sink: Box<dyn Notifier> = match self.clone() {
+            SinkConfig::Notifyd { url, .. } => Box::new(Notifyd {
+                name,
+                http: http.clone(),
+                url,
+            }),
+            SinkConfig::Ntfy {
+                url,
+                topic,
+                token,
+                title,
+                priority,
+                ..
+            } => Box::new(Ntfy {
+                name,
+                http: http.clone(),
+                url,
+                topic,
+                token: token.map(|t| read("token", &t)).transpose()?,
+                title,
+                priority,
+            }),
+            SinkConfig::Gotify {
+                url,
+                token,
+                title,
+                priority,
+                ..
+            } => Box::new(Gotify {
+                name,
+                http: http.clone(),
+                url,
+                token: read("token", &token)?,
+                title,
+                priority,
+            }),
+            SinkConfig::Webhook {
+                url, headers, body, ..
+            } => {
+                let mut registry = Handlebars::new();
+                // Values are inserted inside JSON strings
+                registry.register_escape_fn(|s| {
+                    let quoted = serde_json::to_string(s).unwrap_or_default();
+                    quoted[1..quoted.len() - 1].to_string()
+                });
+                registry
+                    .register_template_string("body", body)
+                    .map_err(|e| format!("sink {}: body: {}", name, e))?;
+                Box::new(Webhook {
+                    name,
+                    http: http.clone(),
+                    url,
+                    headers,
+                    registry,
+                })
+            }
+            SinkConfig::Email {
+                host,
+                port,
+                security,
+                username,
+                password,
+                from,
+                to,
+                subject,
+                ..
+            } => {
+                let mut builder = match security {
+                    SmtpSecurity::Starttls => {
+                        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?
+                    }
+                    SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+                    SmtpSecurity::None => {
+                        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host)
+                    }
+                };
+                builder = builder.timeout(Some(timeout));
+                if let Some(port) = port {
+                    builder = builder.port(port);
+                }
+                if let (Some(user), Some(password)) = (username, password) {
+                    let password = read("password", &password)?;
+                    builder = builder.credentials(Credentials::new(user, password));
+                }
+
+                let from: Mailbox = from
+                    .parse()
+                    .map_err(|e| format!("sink {}: from: {}", name, e))?;
+                let to = to
+                    .iter()
+                    .map(|t| t.parse::<Mailbox>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("sink {}: to: {}", name, e))?;
+                if to.is_empty() {
+                    return Err(format!("sink {}: no recipient", name).into());
+                }
+
+                Box::new(Email {
+                    name,
+                    transport: builder.build(),
+                    from,
+                    to,
+                    subject,
+                })
+            }
+            SinkConfig::Matrix {
+                homeserver,
+                room_id,
+                access_token,
+                ..
+            } => Box::new(Matrix {
+                name,
+                http: http.clone(),
+                homeserver,
+                room_id,
+                access_token: read("access_token", &access_token)?,
+            }),
+            SinkConfig::File { path, .. } => Box::new(File { name, path }),
+            SinkConfig::Stdout { .. } => Box::new(Stdout { name }),
+        };
+
+        Ok(sink)
+    }
+}
+
+fn check_status(response: reqwest::Response) -> Result<(), SinkError> {
+    response.error_for_status()?;
+    Ok(())
+}
+
+struct Notifyd {
+    name: String,
+    http: reqwest::Client,
+    url: String,
+}
+
+#[async_trait]
+impl Notifier for Notifyd {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let text = message.text;
+        let notif_url = format!("{}/notify", self.url);
+        #[derive(Serialize, Debug)]
+        struct NotifyQuery<'a> {
+            text: &'a str,
+        }
+
+        let request = self.http.post(notif_url).json(&NotifyQuery { text });
+
+        check_status(request.send().await?)
+    }
+}
+
+struct Ntfy {
+    name: String,
+    http: reqwest::Client,
+    url: String,
+    topic: String,
+    token: Option<String>,
+    title: Option<String>,
+    priority: Option<u8>,
+}
+
+#[async_trait]
+impl Notifier for Ntfy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let text = message.text;
+        let mut request = self
+            .http
+            .post(format!("{}/{}", self.url.trim_end_matches('/'), self.topic))
+            .body(text.to_string());
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(title) = &self.title {
+            request = request.header("Title", title);
+        }
+        if let Some(priority) = self.priority {
+            request = request.header("Priority", priority.to_string());
+        }
+
+        check_status(request.send().await?)
+    }
+}
+
+struct Gotify {
+    name: String,
+    http: reqwest::Client,
+    url: String,
+    token: String,
+    title: Option<String>,
+    priority: Option<u8>,
+}
+
+#[async_trait]
+impl Notifier for Gotify {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let text = message.text;
+        #[derive(Serialize)]
+        struct GotifyMessage<'a> {
+            message: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            title: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            priority: Option<u8>,
+        }
+
+        let request = self
+            .http
+            .post(format!("{}/message", self.url.trim_end_matches('/')))
+            .header("X-Gotify-Key", &self.token)
+            .json(&GotifyMessage {
+                message: text,
+                title: self.title.as_deref(),
+                priority: self.priority,
+            });
+
+        check_status(request.send().await?)
+    }
+}
+
+struct Webhook {
+    name: String,
+    http: reqwest::Client,
+    url: String,
+    headers: BTreeMap<String, String>,
+    registry: Handlebars<'static>,
+}
+
+#[async_trait]
+impl Notifier for Webhook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let text = message.text;
+        let body = self
+            .registry
+            .render("body", &serde_json::json!({ "text": text }))?;
+
+        let mut request = self
+            .http
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        for (key, value) in self.headers.iter() {
+            request = request.header(key, value);
+        }
+
+        check_status(request.send().await?)
+    }
+}
+
+struct Email {
+    name: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    subject: String,
+}
+
+#[async_trait]
+impl Notifier for Email {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let text = message.text;
+        let mut builder = lettre::Message::builder()
+            .from(self.from.clone())
+            .subject(&self.subject);
+        for to in self.to.iter() {
+            builder = builder.to(to.clone());
+        }
+
+        self.transport.send(builder.body(text.to_string())?).await?;
+
+        Ok(())
+    }
+}
+
+struct Matrix {
+    name: String,
+    http: reqwest::Client,
+    homeserver: String,
+    room_id: String,
+    access_token: String,
+}
+
+#[async_trait]
+impl Notifier for Matrix {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let text = message.text;
+        // The homeserver ignores a transaction it has already seen
+        let txn_id = message.id();
+
+        let mut url = reqwest::Url::parse(&self.homeserver)?;
+        url.path_segments_mut()
+            .map_err(|_| "homeserver cannot be a base URL")?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+
+        let request = self
+            .http
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&serde_json::json!({ "msgtype": "m.text", "body": text }));
+
+        check_status(request.send().await?)
+    }
+}
+
+struct File {
+    name: String,
+    path: PathBuf,
+}
+
+#[async_trait]
+impl Notifier for File {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let line = format!(
+            "{} {}\n",
+            message.created_at.with_timezone(&Local).to_rfc3339(),
+            message.text
+        );
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        // Tokio finishes the write in the background until flushed
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+struct Stdout {
+    name: String,
+}
+
+#[async_trait]
+impl Notifier for Stdout {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &Message<'_>) -> Result<(), SinkError> {
+        let text = message.text;
+        println!("{}", text);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use wiremock::matchers::{body_json, body_string, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const TEXT: &str = "Bonjour ! Il est 8 heures. Batterie 100 %";
+
+    fn message(text: &str) -> Message<'_> {
+        Message {
+            text,
+            created_at: Utc.with_ymd_and_hms(2026, 10, 18, 6, 30, 0).unwrap(),
+        }
+    }
+
+    /// Sink of a `[[sinks]]` entry
+    fn sink(config: &str) -> Box<dyn Notifier> {
+        let config: SinkConfig = toml::from_str(config).unwrap();
+        config
+            .build(&reqwest::Client::new(), Duration::from_secs(5))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn ntfy_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/maison"))
+            .and(header("Authorization", "Bearer tk-ntfy"))
+            .and(header("Title", "Notifier"))
+            .and(header("Priority", "4"))
+            .and(body_string(TEXT))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        std::env::set_var("NOTIFIER_TEST_NTFY_TOKEN", "tk-ntfy");
+        let ntfy = sink(&format!(
+            r#"
+            type = "ntfy"
+            url = "{}/"
+            topic = "maison"
+            token = {{ env = "NOTIFIER_TEST_NTFY_TOKEN" }}
+            title = "Notifier"
+            priority = 4
+            "#,
+            server.uri()
+        ));
+        ntfy.notify(&message(TEXT)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn gotify_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/message"))
+            .and(header("X-Gotify-Key", "tk-gotify"))
+            .and(body_json(serde_json::json!({
+                "message": TEXT,
+                "title": "Maison",
+                "priority": 5,
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        std::env::set_var("NOTIFIER_TEST_GOTIFY_TOKEN", "tk-gotify");
+        let gotify = sink(&format!(
+            r#"
+            type = "gotify"
+            url = "{}"
+            token = {{ env = "NOTIFIER_TEST_GOTIFY_TOKEN" }}
+            title = "Maison"
+            priority = 5
+            "#,
+            server.uri()
+        ));
+        gotify.notify(&message(TEXT)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn webhook_escapes_the_text() {
+        let text = "Il a dit \"bonjour\"\nà C:\\maison <b>&</b>";
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header("Content-Type", "application/json"))
+            .and(header("X-Tenant", "maison"))
+            .and(body_json(serde_json::json!({
+                "content": text,
+                "source": "notifier",
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let webhook = sink(&format!(
+            r#"
+            type = "webhook"
+            url = "{}/hook"
+            headers = {{ X-Tenant = "maison" }}
+            body = '{{"content": "{{{{text}}}}", "source": "notifier"}}'
+            "#,
+            server.uri()
+        ));
+        webhook.notify(&message(text)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn webhook_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let webhook = sink(&format!("type = \"webhook\"\nurl = \"{}\"", server.uri()));
+        assert!(webhook.notify(&message(TEXT)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn matrix_request() {
+        let message = message(TEXT);
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path(format!(
+                "/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/{}",
+                message.id()
+            )))
+            .and(header("Authorization", "Bearer tk-matrix"))
+            .and(body_json(
+                serde_json::json!({ "msgtype": "m.text", "body": TEXT }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"event_id": "$1"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        std::env::set_var("NOTIFIER_TEST_MATRIX_TOKEN", "tk-matrix");
+        let matrix = sink(&format!(
+            r#"
+            type = "matrix"
+            homeserver = "{}/"
+            room_id = "!room:example.org"
+            access_token = {{ env = "NOTIFIER_TEST_MATRIX_TOKEN" }}
+            "#,
+            server.uri()
+        ));
+        matrix.notify(&message).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_line() {
+        let path =
+            std::env::temp_dir().join(format!("notifier-sink-file-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let file = sink(&format!("type = \"file\"\npath = \"{}\"", path.display()));
+
+        let message = message(TEXT);
+        file.notify(&message).await.unwrap();
+        file.notify(&message).await.unwrap();
+
+        // Stamped with the time of the message, not the one of the write
+        let line = format!(
+            "{} {}\n",
+            message.created_at.with_timezone(&Local).to_rfc3339(),
+            TEXT
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), line.repeat(2));
+    }
+}
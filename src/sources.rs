@@ -0,0 +1,726 @@
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use prometheus_http_query::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{PrometheusSource, Secret, Tls};
+use crate::metrics::{self, MetricValue, Series};
+
+pub type SourceError = Box<dyn error::Error + Send + Sync>;
+
+/// Backend answering the queries of the sections
+#[async_trait]
+pub trait MetricSource: Send + Sync {
+    /// Last sample of `query`, `None` when there is none
+    async fn query(&self, query: &str) -> Result<Option<MetricValue>, SourceError>;
+
+    /// Samples of `query` between `start` and `end`, `None` with fewer than two
+    async fn query_range(
+        &self,
+        _query: &str,
+        _start: DateTime<Utc>,
+        _end: DateTime<Utc>,
+        _step: Duration,
+    ) -> Result<Option<Series>, SourceError> {
+        Err("range queries are not supported by this source".into())
+    }
+}
+
+/// Query language of an InfluxDB source
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InfluxLanguage {
+    /// Through `/query`, served by InfluxDB 1 and by InfluxDB 2 with a DBRP mapping
+    #[default]
+    Influxql,
+    /// Through `/api/v2/query`
+    Flux,
+}
+
+/// Layout of a file source
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    Json,
+    Csv,
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+/// One `[sources.<name>]` entry of the configuration besides `prometheus`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SourceConfig {
+    /// VictoriaMetrics single node or cluster, the queries are MetricsQL
+    Victoriametrics {
+        url: String,
+        /// Tenant of a cluster, `accountID` or `accountID:projectID`, queried
+        /// through `/select/<tenant>/prometheus`
+        tenant: Option<String>,
+        #[serde(default = "default_timeout")]
+        timeout: u64,
+        username: Option<String>,
+        password: Option<Secret>,
+        bearer_token: Option<Secret>,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default)]
+        tls: Tls,
+    },
+    /// InfluxDB, the first field of the last row is the value
+    Influxdb {
+        url: String,
+        #[serde(default)]
+        language: InfluxLanguage,
+        /// Database of the InfluxQL queries
+        database: Option<String>,
+        /// Organization of the Flux queries
+        org: Option<String>,
+        /// API token, sent as `Authorization: Token`
+        token: Option<Secret>,
+        #[serde(default = "default_timeout")]
+        timeout: u64,
+    },
+    /// JSON or CSV file read on each query, the format follows the extension
+    /// unless set
+    File {
+        path: PathBuf,
+        format: Option<FileFormat>,
+    },
+    /// Shell command run on each query, the query being the command line and
+    /// the standard output the value
+    Command {
+        #[serde(default = "default_timeout")]
+        timeout: u64,
+    },
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    reqwest::Url::parse(url)
+        .map(|_| ())
+        .map_err(|e| format!("invalid URL '{}': {}", url, e))
+}
+
+impl SourceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SourceConfig::Victoriametrics {
+                url,
+                timeout,
+                username,
+                password,
+                bearer_token,
+                ..
+            } => {
+                check_url(url)?;
+                if *timeout == 0 {
+                    return Err("timeout must be at least 1 second".to_string());
+                }
+                if username.is_some() != password.is_some() {
+                    return Err("username and password go together".to_string());
+                }
+                if password.is_some() && bearer_token.is_some() {
+                    return Err("set either password or bearer_token".to_string());
+                }
+            }
+            SourceConfig::Influxdb {
+                url,
+                language,
+                database,
+                org,
+                timeout,
+                ..
+            } => {
+                check_url(url)?;
+                if *timeout == 0 {
+                    return Err("timeout must be at least 1 second".to_string());
+                }
+                match language {
+                    InfluxLanguage::Influxql if database.is_none() => {
+                        return Err("InfluxQL queries need a database".to_string())
+                    }
+                    InfluxLanguage::Flux if org.is_none() => {
+                        return Err("Flux queries need an org".to_string())
+                    }
+                    _ => {}
+                }
+            }
+            SourceConfig::File { path, format } => {
+                if format.is_none() && FileSource::format_of(path).is_none() {
+                    return Err(format!(
+                        "{}: cannot tell the format from the extension, set format",
+                        path.display()
+                    ));
+                }
+            }
+            SourceConfig::Command { timeout } => {
+                if *timeout == 0 {
+                    return Err("timeout must be at least 1 second".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the source, secrets are read here
+    pub fn build(&self) -> Result<Box<dyn MetricSource>, Box<dyn error::Error>> {
+        Ok(match self {
+            SourceConfig::Victoriametrics {
+                url,
+                tenant,
+                timeout,
+                username,
+                password,
+                bearer_token,
+                headers,
+                tls,
+            } => {
+                let url = url.trim_end_matches('/');
+                let source = PrometheusSource {
+                    url: match tenant {
+                        Some(tenant) => format!("{}/select/{}/prometheus", url, tenant),
+                        None => url.to_string(),
+                    },
+                    timeout: *timeout,
+                    username: username.clone(),
+                    password: password.clone(),
+                    bearer_token: bearer_token.clone(),
+                    headers: headers.clone(),
+                    tls: tls.clone(),
+                    ..PrometheusSource::default()
+                };
+                Box::new(Prometheus::new(metrics::client(&source)?))
+            }
+            SourceConfig::Influxdb {
+                url,
+                language,
+                database,
+                org,
+                token,
+                timeout,
+            } => Box::new(Influx {
+                http: reqwest::Client::builder()
+                    .timeout(Duration::from_secs(*timeout))
+                    .build()?,
+                url: url.trim_end_matches('/').to_string(),
+                language: *language,
+                database: database.clone(),
+                org: org.clone(),
+                token: match token {
+                    Some(secret) => Some(secret.read().map_err(|e| format!("token: {}", e))?),
+                    None => None,
+                },
+            }),
+            SourceConfig::File { path, format } => Box::new(FileSource {
+                path: path.clone(),
+                format: format
+                    .or_else(|| FileSource::format_of(path))
+                    .unwrap_or(FileFormat::Json),
+            }),
+            SourceConfig::Command { timeout } => Box::new(CommandSource {
+                timeout: Duration::from_secs(*timeout),
+            }),
+        })
+    }
+}
+
+/// Last of `samples`, `None` when empty
+fn last(samples: Vec<(DateTime<Utc>, f64)>) -> Option<MetricValue> {
+    samples
+        .into_iter()
+        .max_by_key(|s| s.0)
+        .map(|(timestamp, value)| MetricValue {
+            value,
+            timestamp,
+            labels: HashMap::new(),
+        })
+}
+
+/// Samples between `start` and `end` in time order, `None` with fewer than two
+fn series(
+    mut samples: Vec<(DateTime<Utc>, f64)>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<Series> {
+    samples.retain(|s| s.0 >= start && s.0 <= end && !s.1.is_nan());
+    samples.sort_by_key(|s| s.0);
+    if samples.len() < 2 {
+        return None;
+    }
+    Some(Series { samples })
+}
+
+/// Time given as seconds since the epoch or as RFC 3339
+fn parse_time(value: &serde_json::Value) -> Option<DateTime<Utc>> {
+    match value {
+        serde_json::Value::Number(n) => {
+            let seconds = n.as_f64()?;
+            Utc.timestamp_opt(seconds.trunc() as i64, (seconds.fract() * 1e9) as u32)
+                .single()
+        }
+        serde_json::Value::String(s) => match s.parse::<f64>() {
+            Ok(seconds) => parse_time(&serde_json::json!(seconds)),
+            Err(_) => DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|t| t.with_timezone(&Utc)),
+        },
+        _ => None,
+    }
+}
+
+fn parse_value(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Server speaking the Prometheus HTTP API
+pub struct Prometheus {
+    client: Client,
+}
+
+impl Prometheus {
+    pub fn new(client: Client) -> Prometheus {
+        Prometheus { client }
+    }
+}
+
+#[async_trait]
+impl MetricSource for Prometheus {
+    async fn query(&self, query: &str) -> Result<Option<MetricValue>, SourceError> {
+        Ok(metrics::query(&self.client, query).await?)
+    }
+
+    async fn query_range(
+        &self,
+        query: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: Duration,
+    ) -> Result<Option<Series>, SourceError> {
+        Ok(metrics::query_range(&self.client, query, start, end, step).await?)
+    }
+}
+
+/// InfluxDB server, `{start}` and `{end}` in a range query are replaced by
+/// the RFC 3339 bounds of the window
+struct Influx {
+    http: reqwest::Client,
+    url: String,
+    language: InfluxLanguage,
+    database: Option<String>,
+    org: Option<String>,
+    token: Option<String>,
+}
+
+impl Influx {
+    async fn run(&self, query: &str) -> Result<Vec<(DateTime<Utc>, f64)>, SourceError> {
+        let request = match self.language {
+            InfluxLanguage::Influxql => self.http.get(format!("{}/query", self.url)).query(&[
+                ("db", self.database.as_deref().unwrap_or_default()),
+                ("q", query),
+                ("epoch", "ms"),
+            ]),
+            InfluxLanguage::Flux => self
+                .http
+                .post(format!("{}/api/v2/query", self.url))
+                .query(&[("org", self.org.as_deref().unwrap_or_default())])
+                .header(reqwest::header::ACCEPT, "application/csv")
+                .json(&serde_json::json!({
+                    "query": query,
+                    "type": "flux",
+                    "dialect": { "annotations": [], "header": true },
+                })),
+        };
+        let request = match &self.token {
+            Some(token) => {
+                request.header(reqwest::header::AUTHORIZATION, format!("Token {}", token))
+            }
+            None => request,
+        };
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("InfluxDB answered {}: {}", status, body.trim()).into());
+        }
+
+        match self.language {
+            InfluxLanguage::Influxql => influxql_samples(&body),
+            InfluxLanguage::Flux => flux_samples(&body),
+        }
+    }
+}
+
+/// Samples of the JSON answer of `/query`, times being in milliseconds
+fn influxql_samples(body: &str) -> Result<Vec<(DateTime<Utc>, f64)>, SourceError> {
+    let answer: serde_json::Value = serde_json::from_str(body)?;
+    let result = &answer["results"][0];
+    if let Some(error) = result["error"].as_str().or(answer["error"].as_str()) {
+        return Err(error.into());
+    }
+
+    let mut samples = Vec::new();
+    for series in result["series"].as_array().into_iter().flatten() {
+        for row in series["values"].as_array().into_iter().flatten() {
+            let row = match row.as_array() {
+                Some(r) if r.len() > 1 => r,
+                _ => continue,
+            };
+            let time = row[0]
+                .as_i64()
+                .and_then(|ms| Utc.timestamp_millis_opt(ms).single());
+            if let (Some(time), Some(value)) = (time, parse_value(&row[1])) {
+                samples.push((time, value));
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Samples of the CSV answer of `/api/v2/query`, made of tables each
+/// starting with a header row
+fn flux_samples(body: &str) -> Result<Vec<(DateTime<Utc>, f64)>, SourceError> {
+    let mut samples = Vec::new();
+    let mut columns: Option<(usize, usize)> = None;
+    for line in body.lines().map(str::trim_end) {
+        if line.is_empty() {
+            columns = None;
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').collect();
+        if let (Some(time), Some(value)) = (
+            fields.iter().position(|f| *f == "_time"),
+            fields.iter().position(|f| *f == "_value"),
+        ) {
+            columns = Some((time, value));
+            continue;
+        }
+        if fields.get(1) == Some(&"error") {
+            return Err(line.to_string().into());
+        }
+        let (time, value) = match columns {
+            Some(c) => c,
+            None => continue,
+        };
+        let time = fields
+            .get(time)
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+        let value = fields.get(value).and_then(|v| v.parse::<f64>().ok());
+        if let (Some(time), Some(value)) = (time, value) {
+            samples.push((time.with_timezone(&Utc), value));
+        }
+    }
+    Ok(samples)
+}
+
+#[async_trait]
+impl MetricSource for Influx {
+    async fn query(&self, query: &str) -> Result<Option<MetricValue>, SourceError> {
+        Ok(last(self.run(query).await?))
+    }
+
+    async fn query_range(
+        &self,
+        query: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        _step: Duration,
+    ) -> Result<Option<Series>, SourceError> {
+        let query = crate::catalog::fill(
+            query,
+            &[
+                (
+                    "start",
+                    &start.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                ),
+                (
+                    "end",
+                    &end.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                ),
+            ],
+        );
+        Ok(series(self.run(&query).await?, start, end))
+    }
+}
+
+/// Local file, the query names the value.
+///
+/// In JSON, the query is a dotted path to a number, to an object with `value`
+/// and `timestamp` or to a list of `[timestamp, value]` pairs. In CSV, the
+/// query is the first field of `name,value[,timestamp]` rows. Samples without
+/// a timestamp are dated by the modification time of the file.
+struct FileSource {
+    path: PathBuf,
+    format: FileFormat,
+}
+
+impl FileSource {
+    fn format_of(path: &Path) -> Option<FileFormat> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Some(FileFormat::Json),
+            Some("csv") => Some(FileFormat::Csv),
+            _ => None,
+        }
+    }
+
+    async fn read(&self, query: &str) -> Result<Vec<(DateTime<Utc>, f64)>, SourceError> {
+        let content = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        let modified: DateTime<Utc> = tokio::fs::metadata(&self.path).await?.modified()?.into();
+
+        Ok(match self.format {
+            FileFormat::Json => {
+                let document: serde_json::Value = serde_json::from_str(&content)
+                    .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+                let node = query
+                    .split('.')
+                    .try_fold(&document, |node, key| node.get(key));
+                node.map(|n| json_samples(n, modified)).unwrap_or_default()
+            }
+            FileFormat::Csv => content
+                .lines()
+                .filter_map(|line| {
+                    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                    if fields.first() != Some(&query) {
+                        return None;
+                    }
+                    let value = fields.get(1)?.parse::<f64>().ok()?;
+                    let time = match fields.get(2) {
+                        Some(t) => parse_time(&serde_json::json!(t))?,
+                        None => modified,
+                    };
+                    Some((time, value))
+                })
+                .collect(),
+        })
+    }
+}
+
+fn json_samples(node: &serde_json::Value, modified: DateTime<Utc>) -> Vec<(DateTime<Utc>, f64)> {
+    match node {
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                serde_json::Value::Array(pair) if pair.len() == 2 => {
+                    Some((parse_time(&pair[0])?, parse_value(&pair[1])?))
+                }
+                other => json_samples(other, modified).pop(),
+            })
+            .collect(),
+        serde_json::Value::Object(object) => object
+            .get("value")
+            .and_then(parse_value)
+            .map(|value| {
+                let time = object.get("timestamp").and_then(parse_time);
+                vec![(time.unwrap_or(modified), value)]
+            })
+            .unwrap_or_default(),
+        other => parse_value(other)
+            .map(|value| vec![(modified, value)])
+            .unwrap_or_default(),
+    }
+}
+
+#[async_trait]
+impl MetricSource for FileSource {
+    async fn query(&self, query: &str) -> Result<Option<MetricValue>, SourceError> {
+        Ok(last(self.read(query).await?))
+    }
+
+    async fn query_range(
+        &self,
+        query: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        _step: Duration,
+    ) -> Result<Option<Series>, SourceError> {
+        Ok(series(self.read(query).await?, start, end))
+    }
+}
+
+/// Shell command printing `value [timestamp]`, an empty output meaning no data
+struct CommandSource {
+    timeout: Duration,
+}
+
+#[async_trait]
+impl MetricSource for CommandSource {
+    async fn query(&self, query: &str) -> Result<Option<MetricValue>, SourceError> {
+        let output = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(query)
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(self.timeout, output)
+            .await
+            .map_err(|_| format!("no output within {}s", self.timeout.as_secs()))??;
+        if !output.status.success() {
+            return Err(format!(
+                "{}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split_whitespace();
+        let value = match fields.next() {
+            Some(v) => v
+                .parse::<f64>()
+                .map_err(|_| format!("'{}' is not a number", v))?,
+            None => return Ok(None),
+        };
+        let timestamp = fields
+            .next()
+            .and_then(|t| parse_time(&serde_json::json!(t)));
+
+        Ok(Some(MetricValue {
+            value,
+            timestamp: timestamp.unwrap_or_else(Utc::now),
+            labels: HashMap::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use wiremock::matchers::{header, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn influxql() {
+        let server = MockServer::start().await;
+        Mock::given(path("/query"))
+            .and(query_param("db", "home"))
+            .and(query_param("q", "SELECT last(level) FROM tank"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "results": [{
+                    "statement_id": 0,
+                    "series": [{
+                        "name": "tank",
+                        "columns": ["time", "last"],
+                        "values": [[1791100800000i64, 152.5]],
+                    }],
+                }],
+            })))
+            .mount(&server)
+            .await;
+
+        let source = SourceConfig::Influxdb {
+            url: server.uri(),
+            language: InfluxLanguage::Influxql,
+            database: Some("home".to_string()),
+            org: None,
+            token: None,
+            timeout: 5,
+        }
+        .build()
+        .unwrap();
+
+        let value = source.query("SELECT last(level) FROM tank").await.unwrap();
+        let value = value.unwrap();
+        assert_eq!(value.value, 152.5);
+        assert_eq!(value.timestamp.timestamp(), 1791100800);
+    }
+
+    #[tokio::test]
+    async fn flux() {
+        let server = MockServer::start().await;
+        Mock::given(path("/api/v2/query"))
+            .and(query_param("org", "home"))
+            .and(header("authorization", "Token secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                ",result,table,_time,_value\r\n\
+                 ,_result,0,2026-10-18T06:00:00Z,410\r\n\
+                 ,_result,0,2026-10-18T08:00:00Z,415\r\n",
+            ))
+            .mount(&server)
+            .await;
+
+        std::env::set_var("NOTIFIER_TEST_INFLUX_TOKEN", "secret");
+        let source = SourceConfig::Influxdb {
+            url: server.uri(),
+            language: InfluxLanguage::Flux,
+            database: None,
+            org: Some("home".to_string()),
+            token: Some(Secret::Env("NOTIFIER_TEST_INFLUX_TOKEN".to_string())),
+            timeout: 5,
+        }
+        .build()
+        .unwrap();
+
+        let start = "2026-10-18T00:00:00Z".parse().unwrap();
+        let end = "2026-10-18T12:00:00Z".parse().unwrap();
+        let series = source
+            .query_range("co2", start, end, Duration::from_secs(60))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(series.delta(), 5.0);
+    }
+
+    #[tokio::test]
+    async fn files() {
+        let dir = std::env::temp_dir().join(format!("notifier-sources-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("values.json");
+        std::fs::write(
+            &json,
+            r#"{"tank": {"level": 150, "pressure": {"value": "1.5", "timestamp": 1791100800}}}"#,
+        )
+        .unwrap();
+        let csv = dir.join("values.csv");
+        let mut file = std::fs::File::create(&csv).unwrap();
+        writeln!(file, "name,value,timestamp").unwrap();
+        writeln!(file, "printer,40,1791100800").unwrap();
+        writeln!(file, "printer,55,1791104400").unwrap();
+
+        let build = |path: &Path| {
+            SourceConfig::File {
+                path: path.to_path_buf(),
+                format: None,
+            }
+            .build()
+            .unwrap()
+        };
+        let json = build(&json);
+        assert_eq!(
+            json.query("tank.level").await.unwrap().unwrap().value,
+            150.0
+        );
+        let pressure = json.query("tank.pressure").await.unwrap().unwrap();
+        assert_eq!(
+            (pressure.value, pressure.timestamp.timestamp()),
+            (1.5, 1791100800)
+        );
+        assert_eq!(json.query("tank.volume").await.unwrap(), None);
+
+        let csv = build(&csv);
+        assert_eq!(csv.query("printer").await.unwrap().unwrap().value, 55.0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn command() {
+        let source = SourceConfig::Command { timeout: 5 }.build().unwrap();
+        assert_eq!(source.query("echo 42").await.unwrap().unwrap().value, 42.0);
+        assert_eq!(source.query("true").await.unwrap(), None);
+        assert!(source.query("echo full").await.is_err());
+        assert!(source.query("exit 3").await.is_err());
+    }
+}
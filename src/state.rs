@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error;
+use std::path::PathBuf;
+
+use crate::alerts::RuleState;
+use crate::catalog::{Pick, Usage};
+use crate::delivery::SinkResult;
+
+/// What was sent
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordKind {
+    Announcement,
+    Alert,
+    /// Text given to `serve` through `POST /say`
+    Say,
+}
+
+/// Message sent, with what it was made of and where it went
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Record {
+    pub sent_at: DateTime<Utc>,
+    pub kind: RecordKind,
+    pub text: String,
+    /// Metric values used, by metric or rule name
+    #[serde(default)]
+    pub values: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub phrases: Vec<Pick>,
+    #[serde(default)]
+    pub deliveries: Vec<SinkResult>,
+}
+
+/// Everything remembered between runs
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct State {
+    /// Messages sent, oldest first
+    pub history: Vec<Record>,
+    /// Alert rules, by name
+    pub alerts: BTreeMap<String, RuleState>,
+    /// What the phrase selection remembers
+    pub phrases: Usage,
+}
+
+/// JSON file holding the state, rewritten as a whole on each change
+pub struct StateStore {
+    path: PathBuf,
+    history_days: u64,
+}
+
+impl StateStore {
+    pub fn new(path: PathBuf, history_days: u64) -> StateStore {
+        StateStore { path, history_days }
+    }
+
+    pub fn load(&self) -> Result<State, Box<dyn error::Error>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => Ok(serde_json::from_str(&content)
+                .map_err(|e| format!("{}: {}", self.path.display(), e))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(State::default()),
+            Err(e) => Err(format!("Cannot read {}: {}", self.path.display(), e).into()),
+        }
+    }
+
+    /// Write `state`, dropping the records older than the history window
+    pub fn store(&self, state: &mut State) -> Result<(), Box<dyn error::Error>> {
+        let oldest = Utc::now() - chrono::Duration::days(self.history_days as i64);
+        state.history.retain(|r| r.sent_at >= oldest);
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(state)?)
+            .map_err(|e| format!("Cannot write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Category;
+
+    #[test]
+    fn history_window() {
+        let path = std::env::temp_dir().join(format!("notifier-state-{}.json", std::process::id()));
+        let store = StateStore::new(path.clone(), 7);
+        let record = |days: i64| Record {
+            sent_at: Utc::now() - chrono::Duration::days(days),
+            kind: RecordKind::Announcement,
+            text: format!("{} days ago", days),
+            values: BTreeMap::from([("soc".to_string(), 80.0)]),
+            phrases: vec![Pick {
+                category: Category::Greetings,
+                index: 3,
+            }],
+            deliveries: Vec::new(),
+        };
+
+        let mut state = store.load().unwrap();
+        state.history.push(record(10));
+        state.history.push(record(2));
+        store.store(&mut state).unwrap();
+        let state = store.load().unwrap();
+        assert_eq!(state.history.len(), 1);
+        assert_eq!(state.history[0].text, "2 days ago");
+        assert_eq!(state.history[0].phrases[0].index, 3);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use serde::Serialize;
+use std::error;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::catalog::{Catalog, Category, Text};
+
+/// Templates compiled into the binary, by name
+const EMBEDDED: [(&str, &str); 3] = [
+    ("message", include_str!("../templates/message.hbs")),
+    ("weather", include_str!("../templates/weather.hbs")),
+    ("electricity", include_str!("../templates/electricity.hbs")),
+];
+
+/// Name of the template rendered when a slot does not name one
+pub const DEFAULT_TEMPLATE: &str = "message";
+
+/// `{{pick "category"}}` renders a random phrase of the catalog
+struct PickHelper {
+    catalog: Arc<Catalog>,
+}
+
+impl HelperDef for PickHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .and_then(|v| v.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("pick", 0))?;
+        let category = Category::from_name(name)
+            .ok_or_else(|| RenderErrorReason::Other(format!("Unknown category '{}'", name)))?;
+
+        out.write(self.catalog.pick(category))?;
+        Ok(())
+    }
+}
+
+/// `{{text "name" key=value}}` renders a fixed sentence of the locale, a
+/// null value is written as the `unavailable` text
+struct TextHelper {
+    catalog: Arc<Catalog>,
+}
+
+impl HelperDef for TextHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .and_then(|v| v.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("text", 0))?;
+        let text = Text::from_name(name)
+            .ok_or_else(|| RenderErrorReason::Other(format!("Unknown text '{}'", name)))?;
+
+        let values: Vec<(&str, String)> = h
+            .hash()
+            .iter()
+            .map(|(k, v)| {
+                let value = match v.value() {
+                    serde_json::Value::String(s) => s.to_string(),
+                    // Missing metric
+                    serde_json::Value::Null => self.catalog.text(Text::Unavailable, &[]),
+                    other => other.to_string(),
+                };
+                (*k, value)
+            })
+            .collect();
+        let vars: Vec<(&str, &str)> = values.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+        out.write(&self.catalog.text(text, &vars))?;
+        Ok(())
+    }
+}
+
+/// Message skeletons rendered by the composer
+pub struct Templates {
+    registry: Handlebars<'static>,
+}
+
+impl Templates {
+    /// Embedded templates, each `<name>.hbs` file of `dir` replaces or adds
+    /// the template `<name>`. Templates can include each other with `{{> name}}`.
+    pub fn new(
+        catalog: Arc<Catalog>,
+        dir: Option<&Path>,
+    ) -> Result<Templates, Box<dyn error::Error>> {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(handlebars::no_escape);
+        registry.register_helper(
+            "pick",
+            Box::new(PickHelper {
+                catalog: catalog.clone(),
+            }),
+        );
+        registry.register_helper("text", Box::new(TextHelper { catalog }));
+
+        for (name, source) in EMBEDDED.iter() {
+            registry
+                .register_template_string(name, source)
+                .map_err(|e| format!("Embedded template '{}': {}", name, e))?;
+        }
+
+        if let Some(dir) = dir {
+            let entries = std::fs::read_dir(dir)
+                .map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+
+            for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+                if path.extension().and_then(|e| e.to_str()) != Some("hbs") {
+                    continue;
+                }
+                let name = match path.file_stem().and_then(|s| s.to_str()) {
+                    Some(n) => n.to_string(),
+                    None => continue,
+                };
+                registry
+                    .register_template_file(&name, &path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+        }
+
+        Ok(Templates { registry })
+    }
+
+    /// Add the template `name` from its source
+    pub fn add(&mut self, name: &str, source: &str) -> Result<(), Box<dyn error::Error>> {
+        self.registry
+            .register_template_string(name, source)
+            .map_err(|e| format!("Template '{}': {}", name, e))?;
+        Ok(())
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.registry.has_template(name)
+    }
+
+    /// Render a template, whitespace runs are collapsed so templates can be laid out freely
+    pub fn render<T: Serialize>(
+        &self,
+        name: &str,
+        data: &T,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let text = self
+            .registry
+            .render(name, data)
+            .map_err(|e| format!("Template '{}': {}", name, e))?;
+        Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
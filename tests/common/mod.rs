@@ -0,0 +1,86 @@
+//! Metric scenarios shared by the integration tests
+// Each test binary uses its own part of this module
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use notifier::config::{Queries, Trends};
+use notifier::metrics::{MetricValue, Series};
+use notifier::sources::{MetricSource, SourceError};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Source answering each query with a value, and each range query with
+/// a line from its first to its last value
+pub struct Scenario {
+    pub values: HashMap<String, f64>,
+    pub ranges: HashMap<String, (f64, f64)>,
+}
+
+#[async_trait]
+impl MetricSource for Scenario {
+    async fn query(&self, query: &str) -> Result<Option<MetricValue>, SourceError> {
+        Ok(self.values.get(query).map(|&value| MetricValue {
+            value,
+            timestamp: Utc::now(),
+            labels: HashMap::new(),
+        }))
+    }
+
+    async fn query_range(
+        &self,
+        query: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        _step: Duration,
+    ) -> Result<Option<Series>, SourceError> {
+        Ok(self.ranges.get(query).map(|&(first, last)| Series {
+            samples: vec![(start, first), (end, last)],
+        }))
+    }
+}
+
+/// Warm and clear, full battery exporting to the grid
+pub fn sunny() -> Scenario {
+    let q = Queries::default();
+    let t = Trends::default();
+    Scenario {
+        values: HashMap::from([
+            (q.temperature, 24.0),
+            (q.temperature_max, 28.0),
+            (q.temperature_min, 14.0),
+            (q.wind_speed, 10.0),
+            (q.humidity, 40.0),
+            (q.cloud_cover, 5.0),
+            (q.weather_code, 113.0),
+            (q.battery_soc, 100.0),
+            (q.avg_solar_1h, 3200.0),
+            (q.avg_power_1h, -1500.0),
+        ]),
+        ranges: HashMap::from([
+            (t.temperature, (19.0, 24.0)),
+            (t.battery_soc, (70.0, 100.0)),
+        ]),
+    }
+}
+
+/// Freezing thunderstorm, low battery drawing from the grid
+pub fn storm() -> Scenario {
+    let q = Queries::default();
+    let t = Trends::default();
+    Scenario {
+        values: HashMap::from([
+            (q.temperature, -3.0),
+            (q.temperature_max, 1.0),
+            (q.temperature_min, -6.0),
+            (q.wind_speed, 75.0),
+            (q.humidity, 95.0),
+            (q.cloud_cover, 100.0),
+            (q.weather_code, 389.0),
+            (q.battery_soc, 12.0),
+            (q.avg_solar_1h, 0.0),
+            (q.avg_power_1h, 800.0),
+        ]),
+        ranges: HashMap::from([(t.temperature, (4.0, -3.0)), (t.battery_soc, (35.0, 12.0))]),
+    }
+}
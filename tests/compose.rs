@@ -0,0 +1,66 @@
+//! Composed messages compared with the golden files of `tests/snapshots`,
+//! rewritten by `UPDATE_SNAPSHOTS=1 cargo test`
+
+use chrono::prelude::*;
+use notifier::sources::MetricSource;
+use notifier::{template_at, Config, Context, FixedClock, Locale};
+use std::collections::HashMap;
+use std::path::Path;
+
+mod common;
+
+use common::{storm, sunny, Scenario};
+
+/// Hours with their own phrase in the default template, and 09:00 without
+const HOURS: [u32; 8] = [8, 9, 10, 12, 15, 16, 19, 20];
+
+async fn compose(scenario: Scenario, hour: u32, locale: Locale) -> String {
+    let mut config: Config = toml::from_str("[llm]\nbackend = \"none\"").unwrap();
+    config.locale = locale;
+    config.state.path = std::env::temp_dir().join("notifier-snapshots/state.json");
+    let time = Local.with_ymd_and_hms(2026, 10, 18, hour, 0, 0).unwrap();
+    let sources = HashMap::from([(
+        "prometheus".to_string(),
+        Box::new(scenario) as Box<dyn MetricSource>,
+    )]);
+    let ctx = Context::new(config, sources, Box::new(FixedClock(time))).unwrap();
+    ctx.catalog.seed(42);
+    let template = template_at(&ctx.config, &ctx.clock.now());
+    notifier::compose(&ctx, time, &template).await.unwrap().text
+}
+
+/// Compare with `tests/snapshots/<name>.txt`, written instead when
+/// `UPDATE_SNAPSHOTS` is set
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {}, run with UPDATE_SNAPSHOTS=1", path.display(), e));
+    assert_eq!(actual, expected, "{} changed", name);
+}
+
+#[tokio::test]
+async fn snapshots() {
+    for (name, scenario) in [("sunny", sunny as fn() -> Scenario), ("storm", storm)] {
+        for hour in HOURS {
+            let text = compose(scenario(), hour, Locale::Fr).await;
+            assert_snapshot(&format!("{}_{:02}h", name, hour), &text);
+        }
+    }
+}
+
+#[tokio::test]
+async fn other_locales() {
+    for locale in [Locale::En, Locale::De, Locale::Es] {
+        for (name, scenario) in [("sunny", sunny as fn() -> Scenario), ("storm", storm)] {
+            let text = compose(scenario(), 8, locale).await;
+            assert_snapshot(&format!("{}_{}_08h", locale.name(), name), &text);
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Whole pipeline run against local stand-ins of Prometheus, notifyd and Ollama
+
+use chrono::prelude::*;
+use notifier::config::Queries;
+use notifier::state::{State, StateStore};
+use notifier::templates::DEFAULT_TEMPLATE;
+use notifier::{announce, build_sources, Config, Context, FixedClock};
+use wiremock::matchers::{path, query_param};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+mod common;
+
+use common::{sunny, Scenario};
+
+/// Announcement in the prompt of an Ollama request
+fn announcement(request: &Request) -> String {
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let prompt = body["prompt"].as_str().unwrap_or_default();
+    prompt
+        .rsplit("Announcement: ")
+        .next()
+        .and_then(|m| m.split("\nRewrite:").next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Ollama stand-in answering with the announcement of the prompt, in bold
+/// after an introduction on its own line as small models often write
+struct Echo;
+
+impl Respond for Echo {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let message = announcement(request);
+        // Streamed in two pieces, cut on a character boundary
+        let middle = message
+            .char_indices()
+            .nth(message.chars().count() / 2)
+            .map_or(0, |(i, _)| i);
+        let (start, end) = message.split_at(middle);
+        let pieces = ["Voici une version reformulée :\n\n**", start, end, "**"];
+        let mut lines: Vec<String> = pieces
+            .iter()
+            .map(|p| {
+                serde_json::json!({ "model": "tiny", "response": p, "done": false }).to_string()
+            })
+            .collect();
+        lines
+            .push(serde_json::json!({ "model": "tiny", "response": "", "done": true }).to_string());
+        ResponseTemplate::new(200).set_body_string(lines.join("\n"))
+    }
+}
+
+/// Prometheus answer holding `data`
+fn prometheus_answer(data: serde_json::Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "status": "success",
+        "data": data,
+    }))
+}
+
+/// Local stand-ins of Prometheus, notifyd and Ollama, with a
+/// configuration sending the whole pipeline to them
+struct Harness {
+    prometheus: MockServer,
+    notifyd: MockServer,
+    ollama: MockServer,
+    config: Config,
+}
+
+impl Harness {
+    async fn start(name: &str) -> Harness {
+        let prometheus = MockServer::start().await;
+        let notifyd = MockServer::start().await;
+        let ollama = MockServer::start().await;
+        let dir = std::env::temp_dir().join(format!("notifier-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let config: Config = toml::from_str(&format!(
+            r#"
+            [sources.prometheus]
+            url = "{}"
+            [[sinks]]
+            type = "notifyd"
+            url = "{}"
+            [llm]
+            url = "{}"
+            model = "tiny"
+            timeout = 5
+            [delivery]
+            retries = 0
+            outbox = "{}"
+            [state]
+            path = "{}"
+            "#,
+            prometheus.uri(),
+            notifyd.uri(),
+            ollama.uri(),
+            dir.join("outbox.jsonl").display(),
+            dir.join("state.json").display(),
+        ))
+        .unwrap();
+        config.validate().unwrap();
+
+        // Queries without a canned value have no data
+        Mock::given(path("/api/v1/query"))
+            .respond_with(prometheus_answer(
+                serde_json::json!({ "resultType": "vector", "result": [] }),
+            ))
+            .with_priority(10)
+            .mount(&prometheus)
+            .await;
+        Mock::given(path("/api/v1/query_range"))
+            .respond_with(prometheus_answer(
+                serde_json::json!({ "resultType": "matrix", "result": [] }),
+            ))
+            .with_priority(10)
+            .mount(&prometheus)
+            .await;
+
+        Harness {
+            prometheus,
+            notifyd,
+            ollama,
+            config,
+        }
+    }
+
+    /// Serve the values of `scenario` as vectors sampled now
+    async fn serve(&self, scenario: Scenario) {
+        for (query, value) in scenario.values {
+            Mock::given(path("/api/v1/query"))
+                .and(query_param("query", query.as_str()))
+                .respond_with(prometheus_answer(serde_json::json!({
+                    "resultType": "vector",
+                    "result": [{
+                        "metric": {},
+                        "value": [Utc::now().timestamp(), value.to_string()],
+                    }],
+                })))
+                .mount(&self.prometheus)
+                .await;
+        }
+    }
+
+    async fn rewrite(&self, response: impl Respond + 'static) {
+        Mock::given(path("/api/generate"))
+            .respond_with(response)
+            .mount(&self.ollama)
+            .await;
+    }
+
+    async fn notifyd_status(&self, status: u16) {
+        Mock::given(path("/notify"))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(&self.notifyd)
+            .await;
+    }
+
+    /// Announce at 08:00 as `once` would
+    async fn announce(&self) -> Result<Context, String> {
+        let time = Local.with_ymd_and_hms(2026, 10, 18, 8, 0, 0).unwrap();
+        let sources = build_sources(&self.config).unwrap();
+        let ctx = Context::new(self.config.clone(), sources, Box::new(FixedClock(time))).unwrap();
+        ctx.catalog.seed(1);
+        match announce(&ctx, time, DEFAULT_TEMPLATE).await {
+            Ok(_) => Ok(ctx),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Announcements sent to Ollama for a rewrite
+    async fn prompted(&self) -> Vec<String> {
+        let requests = self.ollama.received_requests().await.unwrap();
+        requests.iter().map(announcement).collect()
+    }
+
+    /// Texts received by notifyd
+    async fn notified(&self) -> Vec<String> {
+        let requests = self.notifyd.received_requests().await.unwrap();
+        requests
+            .iter()
+            .map(|r| {
+                let body: serde_json::Value = serde_json::from_slice(&r.body).unwrap();
+                body["text"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    fn state(&self) -> State {
+        StateStore::new(self.config.state.path.clone(), 30)
+            .load()
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn pipeline() {
+    let harness = Harness::start("pipeline").await;
+    harness.serve(sunny()).await;
+    harness.rewrite(Echo).await;
+    harness.notifyd_status(200).await;
+
+    harness.announce().await.unwrap();
+    let notified = harness.notified().await;
+    assert_eq!(notified.len(), 1);
+    // Without the introduction and the markdown of the answer
+    assert_eq!(notified, harness.prompted().await);
+    assert!(notified[0].contains("Batterie 100 %"), "{}", notified[0]);
+
+    let state = harness.state();
+    let record = &state.history[0];
+    assert_eq!(record.text, notified[0]);
+    assert_eq!(record.values["soc"], 100.0);
+    assert!(!record.phrases.is_empty());
+    assert_eq!(record.deliveries[0].error, None);
+}
+
+#[tokio::test]
+async fn missing_data() {
+    let harness = Harness::start("missing").await;
+    let mut scenario = sunny();
+    let queries = Queries::default();
+    scenario.values.remove(&queries.battery_soc);
+    scenario.values.remove(&queries.temperature);
+    harness.serve(scenario).await;
+    harness.rewrite(Echo).await;
+    harness.notifyd_status(200).await;
+
+    harness.announce().await.unwrap();
+    let notified = harness.notified().await;
+    assert!(!notified[0].contains("Batterie"), "{}", notified[0]);
+    assert!(notified[0].contains("3200 watt heure"), "{}", notified[0]);
+    let values = &harness.state().history[0].values;
+    assert!(!values.contains_key("soc"));
+    assert!(!values.contains_key("temperature"));
+}
+
+#[tokio::test]
+async fn prometheus_errors() {
+    let harness = Harness::start("prometheus").await;
+    Mock::given(path("/api/v1/query"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&harness.prometheus)
+        .await;
+    harness.rewrite(Echo).await;
+    harness.notifyd_status(200).await;
+
+    // The announcement goes out with the date and time only
+    harness.announce().await.unwrap();
+    let notified = harness.notified().await;
+    assert!(
+        notified[0].contains("Dimanche 18 Octobre"),
+        "{}",
+        notified[0]
+    );
+    assert!(!notified[0].contains("Température"), "{}", notified[0]);
+    assert!(harness.state().history[0].values.is_empty());
+}
+
+#[tokio::test]
+async fn ollama_failure() {
+    let harness = Harness::start("ollama").await;
+    harness.serve(sunny()).await;
+    harness
+        .rewrite(ResponseTemplate::new(500).set_body_string("model not found"))
+        .await;
+    harness.notifyd_status(200).await;
+
+    // The composed message is sent as is
+    harness.announce().await.unwrap();
+    let notified = harness.notified().await;
+    assert_eq!(notified, harness.prompted().await);
+    assert!(notified[0].contains("Batterie 100 %"), "{}", notified[0]);
+}
+
+#[tokio::test]
+async fn notifyd_error() {
+    let harness = Harness::start("notifyd").await;
+    harness.serve(sunny()).await;
+    harness.rewrite(Echo).await;
+    harness.notifyd_status(500).await;
+
+    let error = harness.announce().await.err().unwrap();
+    assert_eq!(error, "No sink received the announcement");
+    assert_eq!(harness.notified().await.len(), 1);
+
+    let record = &harness.state().history[0];
+    assert!(record.deliveries[0].error.is_some());
+    let outbox = std::fs::read_to_string(&harness.config.delivery.outbox).unwrap();
+    assert_eq!(outbox.lines().count(), 1);
+}
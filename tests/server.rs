@@ -0,0 +1,173 @@
+//! HTTP API of `serve`, on a local port with the metrics of a scenario
+
+use chrono::prelude::*;
+use notifier::config::Secret;
+use notifier::sources::MetricSource;
+use notifier::state::{RecordKind, StateStore};
+use notifier::{server, Config, Context, FixedClock};
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpListener};
+
+mod common;
+
+use common::sunny;
+
+/// Server answering until the end of the test, with its address and configuration
+fn start(name: &str) -> (SocketAddr, Config) {
+    let dir = std::env::temp_dir().join(format!("notifier-serve-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::env::set_var("NOTIFIER_TEST_SERVER_TOKEN", "secret");
+
+    let mut config: Config = toml::from_str(
+        r#"
+        [llm]
+        backend = "none"
+        [[sinks]]
+        type = "stdout"
+        [delivery]
+        max_age = 0
+        "#,
+    )
+    .unwrap();
+    config.state.path = dir.join("state.json");
+    config.server.token = Some(Secret::Env("NOTIFIER_TEST_SERVER_TOKEN".to_string()));
+
+    let time = Local.with_ymd_and_hms(2026, 10, 18, 9, 30, 0).unwrap();
+    let sources = HashMap::from([(
+        "prometheus".to_string(),
+        Box::new(sunny()) as Box<dyn MetricSource>,
+    )]);
+    let ctx = Context::new(config.clone(), sources, Box::new(FixedClock(time))).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let served = server::serve(ctx, listener, std::future::pending()).await;
+        served.map_err(|e| e.to_string()).unwrap();
+    });
+    (address, config)
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::new()
+}
+
+#[tokio::test]
+async fn health_and_token() {
+    let (address, _) = start("token");
+
+    let health = client()
+        .get(format!("http://{}/health", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(health.status(), 200);
+
+    let preview = client()
+        .get(format!("http://{}/preview", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(preview.status(), 401);
+    let preview = client()
+        .get(format!("http://{}/preview", address))
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(preview.status(), 401);
+}
+
+#[tokio::test]
+async fn preview_of_a_slot() {
+    let (address, config) = start("preview");
+
+    let preview: serde_json::Value = client()
+        .get(format!("http://{}/preview?slot=12:00", address))
+        .bearer_auth("secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let message = preview["message"].as_str().unwrap();
+    assert!(message.contains("Il est 12 heure."), "{}", message);
+    assert_eq!(preview["values"]["soc"], 100.0);
+
+    // Nothing is recorded
+    assert!(!config.state.path.exists());
+
+    // The message comes with why it was not rewritten
+    let preview: serde_json::Value = client()
+        .get(format!("http://{}/preview?slot=12:00&llm=true", address))
+        .bearer_auth("secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(preview["message"]
+        .as_str()
+        .unwrap()
+        .contains("Il est 12 heure."));
+    assert!(preview["rewrite"].is_null());
+    assert_eq!(
+        preview["rewrite_error"],
+        "the rewrite is disabled, [llm] backend is none"
+    );
+
+    let unknown = client()
+        .get(format!("http://{}/preview?template=nope", address))
+        .bearer_auth("secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unknown.status(), 400);
+}
+
+#[tokio::test]
+async fn announce_and_say() {
+    let (address, config) = start("announce");
+
+    let record: serde_json::Value = client()
+        .post(format!("http://{}/announce", address))
+        .bearer_auth("secret")
+        .body(r#"{"template": "electricity"}"#)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let text = record["text"].as_str().unwrap();
+    assert!(text.contains("Batterie 100 %"), "{}", text);
+    assert!(!text.contains("Dimanche"), "{}", text);
+
+    let say = client()
+        .post(format!("http://{}/say", address))
+        .bearer_auth("secret")
+        .body(r#"{"text": "Le dîner est prêt", "rewrite": false}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(say.status(), 200);
+
+    let bad = client()
+        .post(format!("http://{}/say", address))
+        .bearer_auth("secret")
+        .body(r#"{"message": "Le dîner est prêt"}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(bad.status(), 400);
+
+    let history = StateStore::new(config.state.path.clone(), 30)
+        .load()
+        .unwrap()
+        .history;
+    let kinds: Vec<RecordKind> = history.iter().map(|r| r.kind).collect();
+    assert_eq!(kinds, vec![RecordKind::Announcement, RecordKind::Say]);
+    assert_eq!(history[1].text, "Le dîner est prêt");
+}